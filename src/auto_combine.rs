@@ -0,0 +1,136 @@
+//! Combine issue groups whose commits cannot be cherry-picked apart, the way
+//! `--auto-combine` does.
+//!
+//! A group depends on another group when one of its commits conflicts on a
+//! path that an earlier commit in the other group changed. Each such cluster
+//! of groups is combined into one PR, and every other group stays separate.
+
+use std::fmt::Write;
+
+use git2::Commit;
+
+use crate::{
+    autosquash::{self, SquashedCommit},
+    git2_repository::HistoryOrder,
+    issue_group::IssueGroup,
+    issue_group_map::IssueGroupMap,
+    pre_validation::{self, SimulatedConflict},
+};
+
+/// Why one issue group was combined into another.
+#[derive(Debug)]
+pub struct Combination {
+    /// The group the dependent group was combined into.
+    pub issue_group: IssueGroup,
+    /// The group combined into `issue_group`.
+    pub dependent: IssueGroup,
+    /// The commit that could not be picked without the other group.
+    pub commit_summary: String,
+    /// The commit in the other group it depends on.
+    pub dependency_summary: String,
+    pub conflicting_paths: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CombineReport {
+    pub combinations: Vec<Combination>,
+}
+
+impl CombineReport {
+    pub fn is_empty(&self) -> bool {
+        self.combinations.is_empty()
+    }
+
+    pub fn render(&self, _use_color: bool) -> String {
+        let mut output = String::new();
+        for (i, combination) in self.combinations.iter().enumerate() {
+            if i > 0 {
+                writeln!(output).unwrap();
+            }
+            writeln!(
+                output,
+                "note: combined {} into {}",
+                combination.dependent, combination.issue_group
+            )
+            .unwrap();
+            writeln!(output, "  --> commit \"{}\"", combination.commit_summary).unwrap();
+            writeln!(output, "   |").unwrap();
+            for path in &combination.conflicting_paths {
+                writeln!(output, "   = conflict in {}", path).unwrap();
+            }
+            writeln!(output, "   |").unwrap();
+            writeln!(
+                output,
+                "   = note: it depends on commit \"{}\"",
+                combination.dependency_summary
+            )
+            .unwrap();
+        }
+        output
+    }
+}
+
+/// Find the latest commit in another group that `conflict` depends on: an
+/// ancestor of the conflicting commit that changes one of the conflicting
+/// paths.
+fn find_dependency<'a, 'repo>(
+    map: &'a IssueGroupMap<'repo>,
+    issue_group: &IssueGroup,
+    conflict: &SimulatedConflict<'repo>,
+    history: &HistoryOrder,
+    repo: &git2::Repository,
+) -> Option<(&'a IssueGroup, &'a Commit<'repo>)> {
+    map.iter()
+        .filter(|(other, _commits)| *other != issue_group)
+        .flat_map(|(other, commits)| commits.iter().map(move |commit| (other, commit)))
+        .filter(|(_other, commit)| conflict.is_caused_by(commit, repo))
+        .max_by_key(|(_other, commit)| history.key(commit))
+}
+
+/// Find the first issue group that conflicts because of another group.
+fn next_combination<'repo>(
+    map: &IssueGroupMap<'repo>,
+    base_commit: &Commit<'repo>,
+    history: &HistoryOrder,
+    repo: &'repo git2::Repository,
+) -> Option<Combination> {
+    map.iter().find_map(|(issue_group, commits)| {
+        let squashed = autosquash::fold(commits.iter().cloned());
+        let commits = squashed.iter().flat_map(SquashedCommit::commits);
+        let conflict = pre_validation::simulate(commits, base_commit, repo).ok()??;
+        let (dependency_group, dependency) =
+            find_dependency(map, issue_group, &conflict, history, repo)?;
+
+        // Keep the combined group where the earlier of the two groups was
+        let (issue_group, dependent) =
+            match map.position(issue_group) < map.position(dependency_group) {
+                true => (issue_group, dependency_group),
+                false => (dependency_group, issue_group),
+            };
+        Some(Combination {
+            issue_group: issue_group.clone(),
+            dependent: dependent.clone(),
+            commit_summary: conflict.commit.summary().unwrap_or("").to_string(),
+            dependency_summary: dependency.summary().unwrap_or("").to_string(),
+            conflicting_paths: conflict.conflicting_paths,
+        })
+    })
+}
+
+/// Combine issue groups until no group conflicts because of another group.
+///
+/// Conflicts that no other group explains are left for
+/// [`pre_validation::validate`] to report.
+pub fn combine<'repo>(
+    mut map: IssueGroupMap<'repo>,
+    base_commit: &Commit<'repo>,
+    history: &HistoryOrder,
+    repo: &'repo git2::Repository,
+) -> (IssueGroupMap<'repo>, CombineReport) {
+    let mut report = CombineReport::default();
+    while let Some(combination) = next_combination(&map, base_commit, history, repo) {
+        map = map.combine(&combination.issue_group, &combination.dependent, history);
+        report.combinations.push(combination);
+    }
+    (map, report)
+}
@@ -0,0 +1,282 @@
+//! Fold `fixup!`, `squash!` and `amend!` commits into the commit they target,
+//! the way `git rebase --autosquash` does.
+
+use git2::{Commit, Oid};
+
+/// Shortest commit id prefix git accepts as a fixup target.
+const MINIMUM_ID_PREFIX: usize = 4;
+
+/// How a fixup commit changes the commit it is folded into.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FixupKind {
+    /// `fixup!`: keep the target's changes and message, discard this message.
+    Fixup,
+    /// `squash!`: keep the target's changes, and append this message's body.
+    Squash,
+    /// `amend!`: keep the target's changes, and replace its message with this
+    /// message's body.
+    Amend,
+}
+
+impl FixupKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            FixupKind::Fixup => "fixup!",
+            FixupKind::Squash => "squash!",
+            FixupKind::Amend => "amend!",
+        }
+    }
+}
+
+/// The subject of a `fixup!`, `squash!` or `amend!` commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixupSubject<'a> {
+    pub kind: FixupKind,
+    /// The subject or commit id of the target, with every autosquash prefix
+    /// removed.
+    pub target: &'a str,
+}
+
+/// Remove one autosquash prefix from `subject`.
+fn strip_prefix(subject: &str) -> Option<(FixupKind, &str)> {
+    [FixupKind::Fixup, FixupKind::Squash, FixupKind::Amend]
+        .into_iter()
+        .find_map(|kind| {
+            let rest = subject.strip_prefix(kind.prefix())?;
+            rest.starts_with(char::is_whitespace)
+                .then(|| (kind, rest.trim_start()))
+        })
+}
+
+impl<'a> FixupSubject<'a> {
+    pub fn parse(subject: &'a str) -> Option<Self> {
+        let (kind, mut target) = strip_prefix(subject)?;
+        // `fixup! fixup! feat: x` targets `feat: x`, like git does.
+        while let Some((_, rest)) = strip_prefix(target) {
+            target = rest;
+        }
+        Some(Self { kind, target })
+    }
+
+    fn matches_id(&self, commit: &Commit) -> bool {
+        self.target.len() >= MINIMUM_ID_PREFIX
+            && self.target.chars().all(|c| c.is_ascii_hexdigit())
+            && commit.id().to_string().starts_with(self.target)
+    }
+
+    /// Find the commit to fold into among `candidates`, oldest first.
+    ///
+    /// Like git, prefer an exact subject match, then a commit id prefix, then
+    /// a subject prefix.
+    pub fn find_target<'c, 'repo, I>(&self, candidates: I) -> Option<&'c Commit<'repo>>
+    where
+        I: IntoIterator<Item = &'c Commit<'repo>>,
+        I::IntoIter: Clone,
+    {
+        let candidates = candidates.into_iter();
+        candidates
+            .clone()
+            .find(|commit| commit.summary() == Some(self.target))
+            .or_else(|| candidates.clone().find(|commit| self.matches_id(commit)))
+            .or_else(|| {
+                candidates.clone().find(|commit| {
+                    commit
+                        .summary()
+                        .is_some_and(|summary| summary.starts_with(self.target))
+                })
+            })
+    }
+}
+
+/// A fixup commit folded into another commit.
+#[derive(Clone, Debug)]
+pub struct Fixup<'repo> {
+    pub kind: FixupKind,
+    pub commit: Commit<'repo>,
+}
+
+/// A commit, along with the fixup commits folded into it.
+#[derive(Clone, Debug)]
+pub struct SquashedCommit<'repo> {
+    pub commit: Commit<'repo>,
+    pub fixups: Vec<Fixup<'repo>>,
+}
+
+impl<'repo> From<Commit<'repo>> for SquashedCommit<'repo> {
+    fn from(commit: Commit<'repo>) -> Self {
+        Self {
+            commit,
+            fixups: Vec::new(),
+        }
+    }
+}
+
+impl<'repo> SquashedCommit<'repo> {
+    /// Every commit to cherry-pick, the target first.
+    pub fn commits(&self) -> impl Iterator<Item = &Commit<'repo>> {
+        std::iter::once(&self.commit).chain(self.fixups.iter().map(|fixup| &fixup.commit))
+    }
+
+    /// The message of the squashed commit.
+    pub fn message(&self) -> String {
+        fold_message(
+            self.commit.message().unwrap_or_default(),
+            self.fixups
+                .iter()
+                .map(|fixup| (fixup.kind, fixup.commit.message().unwrap_or_default())),
+        )
+    }
+}
+
+/// Everything after a fixup message's subject line.
+fn body(message: &str) -> &str {
+    message
+        .split_once('\n')
+        .map_or("", |(_subject, body)| body.trim())
+}
+
+fn fold_message<'a>(
+    message: &str,
+    fixups: impl IntoIterator<Item = (FixupKind, &'a str)>,
+) -> String {
+    let mut folded = message.trim_end().to_owned();
+    for (kind, fixup_message) in fixups {
+        match kind {
+            FixupKind::Fixup => {}
+            FixupKind::Squash => {
+                let body = body(fixup_message);
+                if !body.is_empty() {
+                    folded = format!("{folded}\n\n{body}");
+                }
+            }
+            FixupKind::Amend => folded = body(fixup_message).to_owned(),
+        }
+    }
+    folded
+}
+
+/// Set aside the fixup commits that target an earlier commit, paired with the
+/// id of the commit they fold into.
+pub fn partition<'repo>(
+    commits: impl IntoIterator<Item = Commit<'repo>>,
+) -> (Vec<Commit<'repo>>, Vec<(Commit<'repo>, Oid)>) {
+    let mut targets: Vec<Commit<'repo>> = Vec::new();
+    let mut fixups = Vec::new();
+    for commit in commits {
+        let target = commit
+            .summary()
+            .and_then(FixupSubject::parse)
+            .and_then(|subject| subject.find_target(&targets))
+            .map(Commit::id);
+        match target {
+            Some(target) => fixups.push((commit, target)),
+            None => targets.push(commit),
+        }
+    }
+    (targets, fixups)
+}
+
+/// Fold each fixup commit into the earlier commit it targets.
+///
+/// Fixup commits without a target are kept as commits of their own.
+pub fn fold<'repo>(commits: impl IntoIterator<Item = Commit<'repo>>) -> Vec<SquashedCommit<'repo>> {
+    let mut squashed: Vec<SquashedCommit<'repo>> = Vec::new();
+    for commit in commits {
+        let target = commit
+            .summary()
+            .and_then(FixupSubject::parse)
+            .and_then(|subject| {
+                let target =
+                    subject.find_target(squashed.iter().map(|squashed| &squashed.commit))?;
+                Some((subject.kind, target.id()))
+            });
+        match target {
+            Some((kind, target)) => squashed
+                .iter_mut()
+                .find(|squashed| squashed.commit.id() == target)
+                .expect("target should be an earlier commit")
+                .fixups
+                .push(Fixup { kind, commit }),
+            None => squashed.push(commit.into()),
+        }
+    }
+    squashed
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fold_message, FixupKind, FixupSubject};
+
+    #[test]
+    fn parse_fixup_subject() {
+        assert_eq!(
+            FixupSubject::parse("fixup! feat: add config"),
+            Some(FixupSubject {
+                kind: FixupKind::Fixup,
+                target: "feat: add config"
+            })
+        );
+    }
+
+    #[test]
+    fn parse_squash_and_amend_subjects() {
+        assert_eq!(
+            FixupSubject::parse("squash! feat: x").map(|subject| subject.kind),
+            Some(FixupKind::Squash)
+        );
+        assert_eq!(
+            FixupSubject::parse("amend! feat: x").map(|subject| subject.kind),
+            Some(FixupKind::Amend)
+        );
+    }
+
+    #[test]
+    fn parse_nested_fixup_subject() {
+        assert_eq!(
+            FixupSubject::parse("fixup! squash! feat: x"),
+            Some(FixupSubject {
+                kind: FixupKind::Fixup,
+                target: "feat: x"
+            })
+        );
+    }
+
+    #[test]
+    fn parse_ordinary_subject() {
+        assert_eq!(FixupSubject::parse("feat: fixup! handling"), None);
+        assert_eq!(FixupSubject::parse("fixup!feat: x"), None);
+    }
+
+    #[test]
+    fn fixup_keeps_message() {
+        assert_eq!(
+            fold_message(
+                "feat: x\n\nBody.\n\nTicket: AB-1\n",
+                [(FixupKind::Fixup, "fixup! feat: x\n\nTypo.\n")]
+            ),
+            "feat: x\n\nBody.\n\nTicket: AB-1"
+        );
+    }
+
+    #[test]
+    fn squash_appends_body() {
+        assert_eq!(
+            fold_message(
+                "feat: x\n\nBody.\n",
+                [(FixupKind::Squash, "squash! feat: x\n\nMore detail.\n")]
+            ),
+            "feat: x\n\nBody.\n\nMore detail."
+        );
+    }
+
+    #[test]
+    fn amend_replaces_message() {
+        assert_eq!(
+            fold_message(
+                "feat: x\n\nBody.\n",
+                [(FixupKind::Amend, "amend! feat: x\n\nfeat: y\n\nNew body.\n")]
+            ),
+            "feat: y\n\nNew body."
+        );
+    }
+}
@@ -2,7 +2,12 @@ use std::fmt::Display;
 
 use sanitize_git_ref::sanitize_git_ref_onelevel;
 
-use crate::issue_group::IssueGroup;
+use crate::{
+    branch_template::{BranchNaming, Placeholder},
+    issue::Issue,
+    issue_group::IssueGroup,
+    scope_group::ConventionalHeader,
+};
 
 /// Characters to be replaced with a hyphen, since they interfere with terminal
 /// tab-completion.
@@ -31,18 +36,151 @@ impl BranchName {
         &self.0
     }
 
-    pub fn from_issue_group(issue_group: &IssueGroup, summary: &str) -> Self {
-        let raw_branch_name = match issue_group {
-            IssueGroup::Issue(issue_group) => format!(
-                "{}-{}",
-                issue_group.issue_identifier(),
-                summary.to_lowercase()
-            ),
+    /// The part of a branch name identifying `issue`, e.g. `AB-1` or
+    /// `other-repo-123`.
+    fn issue_key(issue: &Issue) -> String {
+        match issue {
+            Issue::GitHubCrossRepository {
+                repository, number, ..
+            } => format!("{repository}-{number}"),
+            issue => issue.issue_identifier().to_string(),
+        }
+    }
+
+    /// The part of a branch name identifying `issue_group`, without a summary.
+    fn group_key(issue_group: &IssueGroup) -> String {
+        match issue_group {
+            IssueGroup::Issue(issue) => Self::issue_key(issue),
             IssueGroup::Commit(summary) => summary.0.clone().to_lowercase(),
+            IssueGroup::Named(name) => name.0.replace(char::is_whitespace, "-").to_lowercase(),
+            IssueGroup::Combined(members) => members
+                .iter()
+                .map(Self::group_key)
+                .collect::<Vec<_>>()
+                .join("-"),
+        }
+    }
+
+    /// The part of a branch name summarizing `issue_group`, the only part
+    /// shortened to fit `disjoint.branchMaxLength`.
+    fn slug(issue_group: &IssueGroup, summary: &str, naming: &BranchNaming) -> String {
+        let has_issues = !issue_group.issues().is_empty();
+        match (&naming.template, issue_group) {
+            // Groups named without a summary keep their name as the slug
+            (_, IssueGroup::Named(_)) => Self::group_key(issue_group),
+            (_, IssueGroup::Combined(_)) if !has_issues => Self::group_key(issue_group),
+            (Some(_), IssueGroup::Issue(_) | IssueGroup::Commit(_) | IssueGroup::Combined(_)) => {
+                ConventionalHeader::parse(summary)
+                    .map_or(summary, |header| header.description)
+                    .to_lowercase()
+            }
+            (None, IssueGroup::Issue(_) | IssueGroup::Commit(_) | IssueGroup::Combined(_)) => {
+                summary.to_lowercase()
+            }
+        }
+    }
+
+    /// The value of `placeholder` in a branch name template.
+    fn placeholder_value(
+        placeholder: Placeholder,
+        issue_group: &IssueGroup,
+        summary: &str,
+        slug: &str,
+        naming: &BranchNaming,
+    ) -> String {
+        match placeholder {
+            Placeholder::User => naming.user.clone(),
+            Placeholder::Issue => issue_group
+                .issues()
+                .into_iter()
+                .map(Self::issue_key)
+                .collect::<Vec<_>>()
+                .join("-"),
+            Placeholder::Slug => slug.to_string(),
+            Placeholder::Type => ConventionalHeader::parse(summary)
+                .map_or(String::new(), |header| header.kind.to_lowercase()),
+            Placeholder::Date => naming.date.clone(),
+        }
+    }
+
+    /// The sanitized branch name of `issue_group`, summarized by `slug`.
+    fn render(issue_group: &IssueGroup, summary: &str, slug: &str, naming: &BranchNaming) -> Self {
+        let raw_branch_name = match (&naming.template, issue_group) {
+            (Some(template), _) => template.render(|placeholder| {
+                Self::placeholder_value(placeholder, issue_group, summary, slug, naming)
+            }),
+            (None, IssueGroup::Issue(issue)) => format!("{}-{slug}", Self::issue_key(issue)),
+            // A combined branch is named after every group in it, and after
+            // its summary when it is for an issue
+            (None, IssueGroup::Combined(_)) if !issue_group.issues().is_empty() => {
+                format!("{}-{slug}", Self::group_key(issue_group))
+            }
+            (None, IssueGroup::Commit(_) | IssueGroup::Named(_) | IssueGroup::Combined(_)) => {
+                slug.to_string()
+            }
         };
         Self::new(sanitize_git_ref_onelevel(&raw_branch_name))
     }
 
+    pub fn from_issue_group(
+        issue_group: &IssueGroup,
+        summary: &str,
+        naming: &BranchNaming,
+    ) -> Self {
+        Self::from_issue_group_numbered(issue_group, summary, naming, None)
+    }
+
+    /// Name the branch of `issue_group`, followed by `_<number>` when given,
+    /// to tell apart branches that would otherwise share a name. The number
+    /// counts towards the maximum length.
+    pub fn from_issue_group_numbered(
+        issue_group: &IssueGroup,
+        summary: &str,
+        naming: &BranchNaming,
+        number: Option<u32>,
+    ) -> Self {
+        let suffix = number.map_or(String::new(), |number| format!("_{number}"));
+        let slug = Self::slug(issue_group, summary, naming);
+        let render = |slug: &str| Self::render(issue_group, summary, slug, naming);
+        let branch_name = match naming.max_length {
+            Some(max_length) => Self::fit(&slug, max_length.saturating_sub(suffix.len()), render),
+            None => render(&slug),
+        };
+        Self::new(format!("{branch_name}{suffix}"))
+    }
+
+    /// Shorten `slug` until the branch name `render`s to at most `max_length`
+    /// bytes, cutting at the last word boundary that fits. The rest of the
+    /// name, such as the issue key, is never cut: when it alone is too long,
+    /// the slug is dropped.
+    fn fit(slug: &str, max_length: usize, render: impl Fn(&str) -> Self) -> Self {
+        let fits =
+            |branch_name: &Self| !branch_name.0.is_empty() && branch_name.0.len() <= max_length;
+        let branch_name = render(slug);
+        if fits(&branch_name) {
+            return branch_name;
+        }
+        let word_ends = slug
+            .char_indices()
+            .filter(|(_, c)| c.is_whitespace() || matches!(c, '-' | '/'))
+            .map(|(end, _)| end)
+            .rev();
+        if let Some(branch_name) = word_ends.map(|end| render(&slug[..end])).find(fits) {
+            return branch_name;
+        }
+        let without_slug = render("");
+        if !without_slug.0.is_empty() {
+            return without_slug;
+        }
+        // The slug is the whole name, and its first word is too long
+        (1..slug.len())
+            .rev()
+            .filter(|&end| slug.is_char_boundary(end))
+            .map(|end| render(&slug[..end]))
+            .find(fits)
+            .unwrap_or(branch_name)
+    }
+
     pub fn new(value: String) -> Self {
         let s = value.replace(CHARACTERS_TO_REPLACE_WITH_HYPHEN, "-");
         let s = s.replace(CHARACTERS_TO_REMOVE, "");
@@ -67,8 +205,137 @@ impl From<String> for BranchName {
 #[cfg(test)]
 mod test {
     use super::BranchName;
+    use crate::{
+        branch_template::BranchNaming,
+        issue::Issue,
+        issue_group::{GitCommitSummary, GroupName, IssueGroup},
+    };
     use proptest::prelude::*;
 
+    #[test]
+    fn cross_repository_issue_includes_repository_name() {
+        let issue_group = IssueGroup::Issue(Issue::GitHubCrossRepository {
+            owner: "org".to_string(),
+            repository: "other-repo".to_string(),
+            number: "123".to_string(),
+        });
+        let branch =
+            BranchName::from_issue_group(&issue_group, "Fix: the widget", &BranchNaming::default());
+        assert_eq!(branch.as_str(), "other-repo-123-fix-the-widget");
+    }
+
+    #[test]
+    fn combined_group_includes_every_member() {
+        let issue_group = IssueGroup::Combined(vec![
+            Issue::WorkTracker("AB-1".to_string()).into(),
+            Issue::GitHub("12".to_string()).into(),
+        ]);
+        let branch =
+            BranchName::from_issue_group(&issue_group, "Add config", &BranchNaming::default());
+        assert_eq!(branch.as_str(), "AB-1-12-add-config");
+
+        let issue_group = IssueGroup::Combined(vec![
+            GroupName("docs a".to_string()).into(),
+            GroupName("docs-b".to_string()).into(),
+        ]);
+        let branch =
+            BranchName::from_issue_group(&issue_group, "Fix typo", &BranchNaming::default());
+        assert_eq!(branch.as_str(), "docs-a-docs-b");
+    }
+
+    fn naming(template: &str, max_length: Option<usize>) -> BranchNaming {
+        BranchNaming {
+            template: Some(template.parse().unwrap()),
+            max_length,
+            user: "octocat".to_string(),
+            date: "2001-09-09".to_string(),
+        }
+    }
+
+    #[test]
+    fn template_fills_placeholders() {
+        let issue_group = IssueGroup::Issue(Issue::WorkTracker("ABC-123".to_string()));
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "feat(parser): Add comments",
+            &naming("{user}/{issue}/{type}-{slug}", None),
+        );
+        assert_eq!(branch.as_str(), "octocat/ABC-123/feat-add-comments");
+
+        let issue_group = IssueGroup::Named(GroupName("parser".to_string()));
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "fix: handle empty input",
+            &naming("{user}/{issue}/{slug}", None),
+        );
+        assert_eq!(branch.as_str(), "octocat/parser");
+    }
+
+    #[test]
+    fn max_length_truncates_on_word_boundaries() {
+        let issue_group = IssueGroup::Issue(Issue::WorkTracker("ABC-123".to_string()));
+        let summary = "Support reading configuration from the environment";
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            summary,
+            &naming("{issue}-{slug}", Some(30)),
+        );
+        assert_eq!(branch.as_str(), "ABC-123-support-reading");
+
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            summary,
+            &naming("{issue}-{slug}", Some(26)),
+        );
+        assert_eq!(branch.as_str(), "ABC-123-support-reading");
+
+        let issue_group = IssueGroup::Commit(GitCommitSummary(summary.to_string()));
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "Supportreadingconfiguration",
+            &naming("{slug}", Some(7)),
+        );
+        assert_eq!(branch.as_str(), "support");
+    }
+
+    #[test]
+    fn max_length_never_cuts_the_issue_key() {
+        let issue_group = IssueGroup::Issue(Issue::WorkTracker("ABC-123".to_string()));
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "Support reading configuration",
+            &naming("{issue}-{slug}", Some(5)),
+        );
+        assert_eq!(branch.as_str(), "ABC-123");
+
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "Support reading configuration",
+            &naming("{user}/{issue}/{slug}", Some(10)),
+        );
+        assert_eq!(branch.as_str(), "octocat/ABC-123");
+    }
+
+    #[test]
+    fn truncated_names_are_sanitized() {
+        let issue_group = IssueGroup::Issue(Issue::WorkTracker("ABC-123".to_string()));
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "Regenerate Cargo.lock and package.json. lock files",
+            &naming("{issue}-{slug}", Some(36)),
+        );
+        assert_eq!(branch.as_str(), "ABC-123-regenerate-cargo-and");
+        assert!(git2::Branch::name_is_valid(branch.as_str()).unwrap());
+
+        let branch = BranchName::from_issue_group(
+            &issue_group,
+            "Regenerate Cargo.lock and package.json. lock files",
+            &naming("{issue}-{slug}", Some(46)),
+        );
+        assert_eq!(branch.as_str(), "ABC-123-regenerate-cargo-and-package.json");
+        assert!(git2::Branch::name_is_valid(branch.as_str()).unwrap());
+    }
+
     proptest! {
         #[test]
         fn sanitization_is_idempotent(s in "\\PC*") {
@@ -0,0 +1,252 @@
+//! Branch name templates, configured with `disjoint.branchTemplate`, and the
+//! maximum branch name length, configured with `disjoint.branchMaxLength`.
+//!
+//! A template is a branch name containing placeholders, e.g.
+//! `{user}/{issue}/{slug}`. Path components left empty by a placeholder, such
+//! as `{issue}` for a group without an issue, are dropped, as are hyphens
+//! left dangling by one, such as in `{type}-{slug}` for a commit without a
+//! Conventional Commits type.
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// A value substituted into a branch name template.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Placeholder {
+    /// The GitHub login of the token's owner
+    User,
+    /// The issue key, e.g. `ABC-123`
+    Issue,
+    /// The summary, without its Conventional Commits header
+    Slug,
+    /// The Conventional Commits type, e.g. `feat`
+    Type,
+    /// Today's date, e.g. `2001-09-09`
+    Date,
+}
+
+impl Placeholder {
+    const ALL: [Self; 5] = [Self::User, Self::Issue, Self::Slug, Self::Type, Self::Date];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Issue => "issue",
+            Self::Slug => "slug",
+            Self::Type => "type",
+            Self::Date => "date",
+        }
+    }
+}
+
+/// One piece of a template: literal text, or a placeholder.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BranchTemplate {
+    /// The segments of each `/`-separated path component.
+    components: Vec<Vec<Segment>>,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ParseBranchTemplateError {
+    value: String,
+    kind: ParseBranchTemplateErrorKind,
+}
+
+impl Display for ParseBranchTemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseBranchTemplateErrorKind::UnknownPlaceholder(name) => write!(
+                f,
+                "branch template {:?} contains unknown placeholder {{{name}}}",
+                self.value
+            ),
+            ParseBranchTemplateErrorKind::UnclosedPlaceholder => write!(
+                f,
+                "branch template {:?} contains an unclosed placeholder",
+                self.value
+            ),
+        }
+    }
+}
+
+impl Error for ParseBranchTemplateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseBranchTemplateErrorKind::UnknownPlaceholder(_) => None,
+            ParseBranchTemplateErrorKind::UnclosedPlaceholder => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseBranchTemplateErrorKind {
+    #[non_exhaustive]
+    UnknownPlaceholder(String),
+    #[non_exhaustive]
+    UnclosedPlaceholder,
+}
+
+impl FromStr for BranchTemplate {
+    type Err = ParseBranchTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |kind| ParseBranchTemplateError {
+            value: s.to_owned(),
+            kind,
+        };
+        let components = s
+            .trim()
+            .split('/')
+            .map(|component| {
+                let mut segments = Vec::new();
+                let mut rest = component;
+                while let Some(start) = rest.find('{') {
+                    if start > 0 {
+                        segments.push(Segment::Literal(rest[..start].to_owned()));
+                    }
+                    let end = rest[start..]
+                        .find('}')
+                        .ok_or_else(|| error(ParseBranchTemplateErrorKind::UnclosedPlaceholder))?;
+                    let name = &rest[start + 1..start + end];
+                    let placeholder = Placeholder::ALL
+                        .into_iter()
+                        .find(|placeholder| placeholder.name() == name)
+                        .ok_or_else(|| {
+                            error(ParseBranchTemplateErrorKind::UnknownPlaceholder(
+                                name.to_owned(),
+                            ))
+                        })?;
+                    segments.push(Segment::Placeholder(placeholder));
+                    rest = &rest[start + end + 1..];
+                }
+                if !rest.is_empty() {
+                    segments.push(Segment::Literal(rest.to_owned()));
+                }
+                Ok(segments)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { components })
+    }
+}
+
+impl BranchTemplate {
+    /// Whether the template contains `placeholder`.
+    pub fn contains(&self, placeholder: Placeholder) -> bool {
+        self.components
+            .iter()
+            .flatten()
+            .any(|segment| segment == &Segment::Placeholder(placeholder))
+    }
+
+    /// Substitute `value` for each placeholder, trimming the hyphens left
+    /// around empty values and dropping path components that are left empty.
+    pub fn render(&self, value: impl Fn(Placeholder) -> String) -> String {
+        self.components
+            .iter()
+            .map(|component| {
+                component
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Literal(literal) => literal.clone(),
+                        Segment::Placeholder(placeholder) => value(*placeholder),
+                    })
+                    .collect::<String>()
+                    .trim_matches('-')
+                    .to_string()
+            })
+            .filter(|component| !component.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// How to name branches: the configured template and maximum length, and the
+/// placeholder values shared by every branch.
+#[derive(Clone, Debug, Default)]
+pub struct BranchNaming {
+    pub template: Option<BranchTemplate>,
+    pub max_length: Option<usize>,
+    pub user: String,
+    pub date: String,
+}
+
+/// Format the UTC date of a Unix timestamp, e.g. `2001-09-09`.
+pub fn date(unix_seconds: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = unix_seconds.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{date, BranchTemplate, Placeholder};
+
+    fn render(template: &str, issue: &str) -> String {
+        template
+            .parse::<BranchTemplate>()
+            .unwrap()
+            .render(|placeholder| match placeholder {
+                Placeholder::User => "octocat".to_string(),
+                Placeholder::Issue => issue.to_string(),
+                Placeholder::Slug => "add-config".to_string(),
+                Placeholder::Type => "feat".to_string(),
+                Placeholder::Date => "2001-09-09".to_string(),
+            })
+    }
+
+    #[test]
+    fn render_every_placeholder() {
+        assert_eq!(
+            render("{user}/{issue}/{slug}", "ABC-123"),
+            "octocat/ABC-123/add-config"
+        );
+        assert_eq!(
+            render("{type}/{date}-{slug}", "ABC-123"),
+            "feat/2001-09-09-add-config"
+        );
+    }
+
+    #[test]
+    fn empty_components_are_dropped() {
+        assert_eq!(render("{user}/{issue}/{slug}", ""), "octocat/add-config");
+        assert_eq!(render("{user}/{issue}-{slug}", ""), "octocat/add-config");
+    }
+
+    #[test]
+    fn unknown_placeholder_is_an_error() {
+        assert!("{user}/{ticket}".parse::<BranchTemplate>().is_err());
+        assert!("{user}/{slug".parse::<BranchTemplate>().is_err());
+    }
+
+    #[test]
+    fn template_contains_placeholder() {
+        let template: BranchTemplate = "{user}/{slug}".parse().unwrap();
+        assert!(template.contains(Placeholder::User));
+        assert!(!template.contains(Placeholder::Date));
+    }
+
+    #[test]
+    fn format_dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(1_000_000_000), "2001-09-09");
+        assert_eq!(date(951_782_400), "2000-02-29");
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{ArgAction, Parser};
+use std::path::PathBuf;
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum CommitsToConsider {
@@ -48,6 +50,54 @@ impl From<&str> for OverlayCommitsIntoOnePullRequest {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CombineDependentGroups {
+    Yes,
+    No,
+}
+
+impl From<&str> for CombineDependentGroups {
+    fn from(value: &str) -> Self {
+        match value {
+            "true" => Self::Yes,
+            "false" => Self::No,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StackDependentGroups {
+    Yes,
+    No,
+}
+
+impl From<&str> for StackDependentGroups {
+    fn from(value: &str) -> Self {
+        match value {
+            "true" => Self::Yes,
+            "false" => Self::No,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegroupCommits {
+    Yes,
+    No,
+}
+
+impl From<&str> for RegroupCommits {
+    fn from(value: &str) -> Self {
+        match value {
+            "true" => Self::Yes,
+            "false" => Self::No,
+            _ => unreachable!(),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum CommitGrouping {
     Individual,
@@ -64,9 +114,61 @@ impl From<&str> for CommitGrouping {
     }
 }
 
+/// How to group commits without an issue.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    /// Give each commit its own group, when --all is active
+    #[default]
+    Commit,
+    /// Group commits by CODEOWNERS owners, or else by top-level directory
+    Path,
+    /// Group commits by the scope of their Conventional Commits header
+    Scope,
+    /// Group commits by the type and scope of their Conventional Commits header
+    TypeScope,
+}
+
+/// How to group a commit that references more than one issue.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum MultipleIssuePolicy {
+    /// Group the commit under the first issue recognized in its message
+    #[default]
+    Primary,
+    /// Stop and list the issues the commit references
+    Refuse,
+}
+
+/// Work to do instead of creating branches and pull requests right away.
+#[derive(Clone, Debug, Eq, PartialEq, Subcommand)]
+pub enum Command {
+    /// Write the branches and pull requests git-disjoint would create to a
+    /// plan file, without taking any action.
+    ///
+    /// The plan lists each branch's commits, the branch it is built on, and
+    /// the title and body of its pull request. Edit it, then create exactly
+    /// what it describes with `git disjoint apply`.
+    Plan {
+        /// The file to write the plan to.
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Create the branches and pull requests described by a plan file.
+    ///
+    /// Every commit in the plan must exist, and every branch must cherry-pick
+    /// cleanly, before any branch is created.
+    Apply {
+        /// The plan file written by `git disjoint plan`.
+        #[arg(value_name = "FILE")]
+        plan: PathBuf,
+    },
+}
+
 #[derive(Clone, Debug, Parser)]
 #[command(author, version, about)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Do not ignore commits without an issue trailer.
     ///
     /// Commits without an issue trailer are considered to be their own
@@ -78,55 +180,181 @@ pub struct Cli {
     #[arg(
         short,
         long,
+        global = true,
         help = "Consider every commit, even commits without an issue trailer",
         action = ArgAction::SetTrue,
     )]
     pub all: CommitsToConsider,
 
+    /// Combine issue groups that cannot be split apart.
+    ///
+    /// When a commit would not cherry-pick cleanly onto the base because it
+    /// builds on changes from another issue group, combine the two groups
+    /// into one PR instead of stopping. Groups without such a dependency stay
+    /// separate.
+    ///
+    /// git-disjoint explains each combination it makes.
+    #[arg(
+        long,
+        global = true,
+        help = "Combine issue groups that depend on each other into one PR",
+        action = ArgAction::SetTrue,
+    )]
+    pub auto_combine: CombineDependentGroups,
+
     /// The starting point (exclusive) of commits to act on.
     ///
     /// Defaults to the repository's default branch.
     #[arg(
         short,
         long,
+        global = true,
         help = "The starting point (exclusive) of commits to act on",
         value_name = "REF"
     )]
     pub base: Option<String>,
 
-    /// Prompt the user to select which issues to create PRs for.
+    /// Prompt the user to select which commits to create PRs for.
     ///
-    /// Select a whitelist of issues (or commits, if the --all flag is active)
-    /// in a terminal UI.
+    /// Select a whitelist of commits in a terminal UI. Each commit is shown
+    /// with its issue group, short hash, author and diffstat, so typing an
+    /// issue or a hash filters the menu. Issue groups with no selected commit
+    /// are skipped.
     #[arg(
         short,
         long,
-        help = "Prompt the user to select which issues to create PRs for",
+        global = true,
+        help = "Prompt the user to select which commits to create PRs for",
         action = ArgAction::SetTrue,
     )]
     pub choose: PromptUserToChooseCommits,
 
+    /// Combine the given issue groups into one PR.
+    ///
+    /// The value is a comma-separated list of issue keys such as `AB-1`,
+    /// GitHub issues such as `#12`, or group names. Repeat to create several
+    /// combined PRs in one run. A combined PR's branch is named after every
+    /// group in it, and its title and body list every issue it covers. Groups
+    /// not named by any --combine keep their own PRs.
+    #[arg(
+        long,
+        global = true,
+        help = "Combine the given issue groups into one PR (repeatable)",
+        value_name = "GROUPS",
+        conflicts_with = "overlay"
+    )]
+    pub combine: Vec<String>,
+
+    /// Create a PR for the given commit.
+    ///
+    /// Repeat to select several commits. The value is any revision git
+    /// understands, such as a hash. The commit's fixup commits are selected
+    /// with it. A commit that is not one of the commits to publish is an
+    /// error.
+    #[arg(
+        long = "commit",
+        global = true,
+        help = "Create a PR for the given commit (repeatable)",
+        value_name = "REV"
+    )]
+    pub commits: Vec<String>,
+
     /// Show the work that would be performed without taking any action.
     #[arg(
         short,
         long,
+        global = true,
         env = "GIT_DISJOINT_DRY_RUN",
         help = "Show the work that would be performed without taking any action"
     )]
     pub dry_run: bool,
 
+    /// Do not create a PR for the given issue.
+    ///
+    /// Repeat to exclude several issues. Every other issue group is still
+    /// selected, unless --issue or --commit narrows the selection. An issue
+    /// that no commit references is an error.
+    #[arg(
+        long = "exclude-issue",
+        global = true,
+        help = "Do not create a PR for the given issue (repeatable)",
+        value_name = "ISSUE"
+    )]
+    pub excluded_issues: Vec<String>,
+
     /// GitHub API token with repo permissions.
     ///
     /// If not provided, git-disjoint will attempt to resolve a token
     /// from the GitHub CLI (`gh auth token`).
     #[arg(
         long,
+        global = true,
         env = "GITHUB_TOKEN",
         help = "GitHub API token [default: resolved from gh auth token]",
         value_name = "TOKEN"
     )]
     pub github_token: Option<String>,
 
+    /// How to group commits without an issue.
+    ///
+    /// With `commit`, each commit without an issue is its own group when --all
+    /// is active, and is ignored otherwise.
+    ///
+    /// With `path`, commits without an issue are grouped by the paths they
+    /// touch: by the set of owners of those paths when the repository has a
+    /// CODEOWNERS file, and by their top-level directories otherwise.
+    ///
+    /// With `scope`, commits whose subject follows Conventional Commits, such
+    /// as `fix(parser): handle empty input`, are grouped by scope (`parser`).
+    /// With `type-scope`, they are grouped by scope and type (`parser-fixes`).
+    /// Commits without a scope are grouped by type, and other commits are
+    /// treated as with `commit`.
+    ///
+    /// Commits with an issue keep their issue grouping, unless git config
+    /// `disjoint.groupByPrecedence` is `group`.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = GroupBy::Commit,
+        help = "How to group commits without an issue",
+        value_name = "MODE",
+        conflicts_with = "separate"
+    )]
+    pub group_by: GroupBy,
+
+    /// Create a PR for the given issue.
+    ///
+    /// Repeat to select several issues. The value is an issue key such as
+    /// `AB-123`, a GitHub issue such as `#12`, or a `Disjoint-Group` name. An
+    /// issue that no commit references is an error, so a typo cannot silently
+    /// produce no PRs.
+    #[arg(
+        long = "issue",
+        global = true,
+        help = "Create a PR for the given issue (repeatable)",
+        value_name = "ISSUE"
+    )]
+    pub issues: Vec<String>,
+
+    /// How to group commits that reference more than one issue.
+    ///
+    /// With `primary`, a commit is grouped under the first issue recognized in
+    /// its message, and every other issue it references is listed as a "Refs"
+    /// link in the PR body.
+    ///
+    /// With `refuse`, git-disjoint stops before taking any action and lists
+    /// the issues referenced by the offending commit.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = MultipleIssuePolicy::Primary,
+        help = "How to group commits that reference more than one issue",
+        value_name = "POLICY"
+    )]
+    pub multiple_issues: MultipleIssuePolicy,
+
     /// Combine multiple issue groups into one PR.
     ///
     /// When this flag is active, git-disjoint will create only one PR.
@@ -136,6 +364,7 @@ pub struct Cli {
     #[arg(
         short,
         long,
+        global = true,
         help = "Combine multiple issue groups into one PR",
         action = ArgAction::SetTrue,
     )]
@@ -148,10 +377,25 @@ pub struct Cli {
     #[arg(
         short,
         long,
+        global = true,
         help = "Create pull requests as ready for review instead of draft"
     )]
     pub ready: bool,
 
+    /// Regroup commits in a full-screen editor before creating any branch.
+    ///
+    /// Each issue group is shown as a column of commits. Move commits between
+    /// groups, reorder them, split groups and rename branches. Groups that
+    /// would not cherry-pick cleanly onto the base are flagged after every
+    /// change.
+    #[arg(
+        long,
+        global = true,
+        help = "Regroup commits in a full-screen editor before creating branches",
+        action = ArgAction::SetTrue,
+    )]
+    pub regroup: RegroupCommits,
+
     /// Do not group commits by issue.
     ///
     /// Treat each commit independently, regardless of issue trailer. Each
@@ -165,10 +409,39 @@ pub struct Cli {
     #[arg(
         short,
         long,
+        global = true,
         help = "Treat every commit separately; do not group by issue",
         action = ArgAction::SetTrue,
     )]
     pub separate: CommitGrouping,
+
+    /// Stack issue groups that cannot be split apart.
+    ///
+    /// When a commit would not cherry-pick cleanly onto the base because it
+    /// builds on changes from another issue group, build its branch on top of
+    /// that group's branch instead of stopping, and open its PR against that
+    /// branch. Each PR body lists the stack it is part of.
+    #[arg(
+        long,
+        global = true,
+        help = "Stack issue groups that depend on each other as chained PRs",
+        action = ArgAction::SetTrue,
+        conflicts_with = "auto_combine",
+    )]
+    pub stack: StackDependentGroups,
+
+    /// Push rebuilt branches without asking for confirmation.
+    ///
+    /// When a published branch is rebuilt because its commits were amended
+    /// or reworded, git-disjoint shows how the branch changes and asks before
+    /// force-pushing it. With this flag, it pushes without asking.
+    #[arg(
+        short,
+        long,
+        global = true,
+        help = "Force-push rebuilt branches without asking for confirmation"
+    )]
+    pub yes: bool,
 }
 
 #[cfg(test)]
@@ -186,4 +459,79 @@ mod tests {
         let cli = Cli::try_parse_from(["git-disjoint", "--github-token", "ghp_abc123"]).unwrap();
         assert_eq!(cli.github_token, Some("ghp_abc123".into()));
     }
+
+    #[test]
+    fn parse_without_multiple_issues_uses_primary() {
+        let cli = Cli::try_parse_from(["git-disjoint"]).unwrap();
+        assert_eq!(cli.multiple_issues, MultipleIssuePolicy::Primary);
+    }
+
+    #[test]
+    fn parse_multiple_issues_refuse() {
+        let cli = Cli::try_parse_from(["git-disjoint", "--multiple-issues", "refuse"]).unwrap();
+        assert_eq!(cli.multiple_issues, MultipleIssuePolicy::Refuse);
+    }
+
+    #[test]
+    fn parse_plan_with_flags_after_subcommand() {
+        let cli = Cli::try_parse_from(["git-disjoint", "plan", "--all", "--output", "plan.toml"])
+            .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Command::Plan {
+                output: PathBuf::from("plan.toml")
+            })
+        );
+        assert_eq!(cli.all, CommitsToConsider::All);
+    }
+
+    #[test]
+    fn parse_apply() {
+        let cli = Cli::try_parse_from(["git-disjoint", "apply", "plan.toml"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Command::Apply {
+                plan: PathBuf::from("plan.toml")
+            })
+        );
+    }
+
+    #[test]
+    fn parse_repeated_selection_flags() {
+        let cli = Cli::try_parse_from([
+            "git-disjoint",
+            "--issue",
+            "AB-1",
+            "--issue",
+            "#12",
+            "--exclude-issue",
+            "AB-9",
+            "--commit",
+            "abc1234",
+        ])
+        .unwrap();
+        assert_eq!(cli.issues, ["AB-1", "#12"]);
+        assert_eq!(cli.excluded_issues, ["AB-9"]);
+        assert_eq!(cli.commits, ["abc1234"]);
+    }
+
+    #[test]
+    fn parse_repeated_combine() {
+        let cli = Cli::try_parse_from([
+            "git-disjoint",
+            "--combine",
+            "AB-1,AB-2",
+            "--combine",
+            "docs-a,docs-b",
+        ])
+        .unwrap();
+        assert_eq!(cli.combine, ["AB-1,AB-2", "docs-a,docs-b"]);
+    }
+
+    #[test]
+    fn combine_conflicts_with_overlay() {
+        assert!(
+            Cli::try_parse_from(["git-disjoint", "--combine", "AB-1,AB-2", "--overlay"]).is_err()
+        );
+    }
 }
@@ -0,0 +1,380 @@
+//! User configuration read from git config (`git config disjoint.*`).
+
+use std::{error::Error, fmt::Display, num::NonZeroUsize};
+
+use regex::Regex;
+
+use crate::{
+    branch_template::{self, BranchTemplate},
+    issue::{self, IssuePattern, IssuePatterns},
+    local_commit::LocalCommitPatterns,
+    tracker_url::{self, TrackerUrl, TrackerUrls},
+};
+
+/// Multi-valued key holding additional issue patterns, each of the form
+/// `<kind>:[<scope>:]<regex>`.
+const ISSUE_PATTERN_KEY: &str = "disjoint.issuePattern";
+
+/// Multi-valued key holding regexes for the subjects of local-only commits.
+const LOCAL_SUBJECT_PATTERN_KEY: &str = "disjoint.localSubjectPattern";
+
+/// Multi-valued key holding work tracker URL templates, each of the form
+/// `<project>:<template>`.
+const TRACKER_URL_KEY: &str = "disjoint.trackerUrl";
+
+/// Single-valued key deciding whether an issue trailer or the `--group-by`
+/// group decides the group of a commit that has both.
+const GROUP_BY_PRECEDENCE_KEY: &str = "disjoint.groupByPrecedence";
+
+/// Single-valued key holding the template for branch names, e.g.
+/// `{user}/{issue}/{slug}`.
+const BRANCH_TEMPLATE_KEY: &str = "disjoint.branchTemplate";
+
+/// Single-valued key holding the maximum length of a branch name.
+const BRANCH_MAX_LENGTH_KEY: &str = "disjoint.branchMaxLength";
+
+/// Git's own setting for the characters that separate trailer keys from values.
+const TRAILER_SEPARATORS_KEY: &str = "trailer.separators";
+
+/// Whether a commit's issue or its `--group-by` group decides its group, when
+/// it has both.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum GroupByPrecedence {
+    /// Group the commit by its issue
+    #[default]
+    Issue,
+    /// Group the commit by its `--group-by` group
+    Group,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub branch_max_length: Option<usize>,
+    pub branch_template: Option<BranchTemplate>,
+    pub group_by_precedence: GroupByPrecedence,
+    pub issue_patterns: IssuePatterns,
+    pub local_commit_patterns: LocalCommitPatterns,
+    pub tracker_urls: TrackerUrls,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct FromGitConfigError {
+    key: &'static str,
+    kind: FromGitConfigErrorKind,
+}
+
+impl Display for FromGitConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            FromGitConfigErrorKind::Git(_) => write!(f, "unable to read git config {}", self.key),
+            FromGitConfigErrorKind::IssuePattern(_)
+            | FromGitConfigErrorKind::LocalSubjectPattern(_)
+            | FromGitConfigErrorKind::TrackerUrl(_)
+            | FromGitConfigErrorKind::BranchTemplate(_)
+            | FromGitConfigErrorKind::BranchMaxLength(_) => {
+                write!(f, "invalid value for git config {}", self.key)
+            }
+            FromGitConfigErrorKind::GroupByPrecedence(value) => write!(
+                f,
+                "invalid value {value:?} for git config {}, expected \"issue\" or \"group\"",
+                self.key
+            ),
+        }
+    }
+}
+
+impl Error for FromGitConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            FromGitConfigErrorKind::Git(err) => Some(err),
+            FromGitConfigErrorKind::IssuePattern(err) => Some(err),
+            FromGitConfigErrorKind::LocalSubjectPattern(err) => Some(err),
+            FromGitConfigErrorKind::TrackerUrl(err) => Some(err),
+            FromGitConfigErrorKind::GroupByPrecedence(_) => None,
+            FromGitConfigErrorKind::BranchTemplate(err) => Some(err),
+            FromGitConfigErrorKind::BranchMaxLength(err) => Some(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FromGitConfigErrorKind {
+    #[non_exhaustive]
+    Git(git2::Error),
+    #[non_exhaustive]
+    IssuePattern(issue::ParseIssuePatternError),
+    #[non_exhaustive]
+    LocalSubjectPattern(regex::Error),
+    #[non_exhaustive]
+    TrackerUrl(tracker_url::ParseTrackerUrlError),
+    #[non_exhaustive]
+    GroupByPrecedence(String),
+    #[non_exhaustive]
+    BranchTemplate(branch_template::ParseBranchTemplateError),
+    #[non_exhaustive]
+    BranchMaxLength(std::num::ParseIntError),
+}
+
+/// Read every value of a multi-valued config key, in the order git reports them.
+fn multivar(config: &git2::Config, key: &'static str) -> Result<Vec<String>, FromGitConfigError> {
+    let error = |err| FromGitConfigError {
+        key,
+        kind: FromGitConfigErrorKind::Git(err),
+    };
+    let mut entries = match config.multivar(key, None) {
+        Ok(entries) => entries,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(error(err)),
+    };
+    let mut values = Vec::new();
+    while let Some(entry) = entries.next() {
+        let entry = entry.map_err(error)?;
+        if let Some(value) = entry.value() {
+            values.push(value.to_owned());
+        }
+    }
+    Ok(values)
+}
+
+/// Read a single-valued config key, if it is set.
+fn string(config: &git2::Config, key: &'static str) -> Result<Option<String>, FromGitConfigError> {
+    match config.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(FromGitConfigError {
+            key,
+            kind: FromGitConfigErrorKind::Git(err),
+        }),
+    }
+}
+
+impl TryFrom<&git2::Config> for Config {
+    type Error = FromGitConfigError;
+
+    fn try_from(config: &git2::Config) -> Result<Self, Self::Error> {
+        let issue_patterns = multivar(config, ISSUE_PATTERN_KEY)?
+            .iter()
+            .map(|value| value.parse::<IssuePattern>())
+            .collect::<Result<IssuePatterns, _>>()
+            .map_err(|err| FromGitConfigError {
+                key: ISSUE_PATTERN_KEY,
+                kind: FromGitConfigErrorKind::IssuePattern(err),
+            })?;
+        let issue_patterns = match string(config, TRAILER_SEPARATORS_KEY)? {
+            Some(separators) => issue_patterns.with_trailer_separators(separators),
+            None => issue_patterns,
+        };
+
+        let local_commit_patterns = multivar(config, LOCAL_SUBJECT_PATTERN_KEY)?
+            .iter()
+            .map(|value| Regex::new(value))
+            .collect::<Result<LocalCommitPatterns, _>>()
+            .map_err(|err| FromGitConfigError {
+                key: LOCAL_SUBJECT_PATTERN_KEY,
+                kind: FromGitConfigErrorKind::LocalSubjectPattern(err),
+            })?;
+
+        let tracker_urls = multivar(config, TRACKER_URL_KEY)?
+            .iter()
+            .map(|value| value.parse::<TrackerUrl>())
+            .collect::<Result<TrackerUrls, _>>()
+            .map_err(|err| FromGitConfigError {
+                key: TRACKER_URL_KEY,
+                kind: FromGitConfigErrorKind::TrackerUrl(err),
+            })?;
+
+        let group_by_precedence = match string(config, GROUP_BY_PRECEDENCE_KEY)?.as_deref() {
+            None | Some("issue") => GroupByPrecedence::Issue,
+            Some("group") => GroupByPrecedence::Group,
+            Some(value) => {
+                return Err(FromGitConfigError {
+                    key: GROUP_BY_PRECEDENCE_KEY,
+                    kind: FromGitConfigErrorKind::GroupByPrecedence(value.to_owned()),
+                })
+            }
+        };
+
+        let branch_template = string(config, BRANCH_TEMPLATE_KEY)?
+            .map(|value| value.parse::<BranchTemplate>())
+            .transpose()
+            .map_err(|err| FromGitConfigError {
+                key: BRANCH_TEMPLATE_KEY,
+                kind: FromGitConfigErrorKind::BranchTemplate(err),
+            })?;
+
+        let branch_max_length = string(config, BRANCH_MAX_LENGTH_KEY)?
+            .map(|value| value.trim().parse::<NonZeroUsize>())
+            .transpose()
+            .map_err(|err| FromGitConfigError {
+                key: BRANCH_MAX_LENGTH_KEY,
+                kind: FromGitConfigErrorKind::BranchMaxLength(err),
+            })?
+            .map(NonZeroUsize::get);
+
+        Ok(Self {
+            branch_max_length,
+            branch_template,
+            group_by_precedence,
+            issue_patterns,
+            local_commit_patterns,
+            tracker_urls,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::{Config, GroupByPrecedence};
+    use crate::issue::Issue;
+
+    fn git_config(dir: &Path, contents: &str) -> git2::Config {
+        let path = dir.join("config");
+        std::fs::write(&path, contents).unwrap();
+        git2::Config::open(&path).unwrap()
+    }
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(tempdir.path(), "")).unwrap();
+        assert_eq!(
+            config
+                .issue_patterns
+                .parse_from_commit_message("feat: x\n\nTicket: AB-1"),
+            Some(Issue::WorkTracker("AB-1".to_string()))
+        );
+        assert_eq!(config.group_by_precedence, GroupByPrecedence::Issue);
+        assert_eq!(config.branch_template, None);
+        assert_eq!(config.branch_max_length, None);
+    }
+
+    #[test]
+    fn reads_every_issue_pattern() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\
+             \tissuePattern = \"ticket:^Jira:\\\\s+(?P<id>\\\\S+)\"\n\
+             \tissuePattern = \"ticket:^Story:\\\\s+(?P<id>\\\\S+)\"\n",
+        ))
+        .unwrap();
+        let patterns = &config.issue_patterns;
+        assert_eq!(
+            patterns.parse_from_commit_message("feat: x\n\nJira: AB-2"),
+            Some(Issue::WorkTracker("AB-2".to_string()))
+        );
+        assert_eq!(
+            patterns.parse_from_commit_message("feat: x\n\nStory: ST-3"),
+            Some(Issue::WorkTracker("ST-3".to_string()))
+        );
+    }
+
+    #[test]
+    fn reads_trailer_separators() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(
+            tempdir.path(),
+            "[trailer]\n\tseparators = \"=:\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            config
+                .issue_patterns
+                .parse_from_commit_message("feat: x\n\nTicket= AB-4"),
+            Some(Issue::WorkTracker("AB-4".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_local_subject_pattern_is_an_error() {
+        let tempdir = TempDir::new().unwrap();
+        let result = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tlocalSubjectPattern = \"^(WIP\"\n",
+        ));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reads_tracker_urls() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\ttrackerUrl = \"ABC:https://acme.atlassian.net/browse/{id}\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            config
+                .tracker_urls
+                .url(&Issue::WorkTracker("ABC-5".to_string())),
+            Some("https://acme.atlassian.net/browse/ABC-5".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_issue_pattern_is_an_error() {
+        let tempdir = TempDir::new().unwrap();
+        let result = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tissuePattern = \"^Jira:\"\n",
+        ));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reads_group_by_precedence() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tgroupByPrecedence = group\n",
+        ))
+        .unwrap();
+        assert_eq!(config.group_by_precedence, GroupByPrecedence::Group);
+    }
+
+    #[test]
+    fn invalid_group_by_precedence_is_an_error() {
+        let tempdir = TempDir::new().unwrap();
+        let result = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tgroupByPrecedence = scope\n",
+        ));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reads_branch_template_and_max_length() {
+        let tempdir = TempDir::new().unwrap();
+        let config = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tbranchTemplate = \"{user}/{issue}/{slug}\"\n\tbranchMaxLength = 40\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            config.branch_template,
+            Some("{user}/{issue}/{slug}".parse().unwrap())
+        );
+        assert_eq!(config.branch_max_length, Some(40));
+    }
+
+    #[test]
+    fn invalid_branch_template_or_max_length_is_an_error() {
+        let tempdir = TempDir::new().unwrap();
+        let result = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tbranchTemplate = \"{user}/{ticket}\"\n",
+        ));
+        assert!(result.is_err());
+
+        let result = Config::try_from(&git_config(
+            tempdir.path(),
+            "[disjoint]\n\tbranchMaxLength = 0\n",
+        ));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Dependencies between issue groups declared with a `Depends-On:` trailer.
+//!
+//! A dependent group's branch is built on the branch of the group it depends
+//! on, since the dependency may be semantic rather than textual and so
+//! invisible to pre-validation.
+
+use std::{collections::HashSet, error::Error, fmt::Display};
+
+use crate::{
+    autosquash::SquashedCommit, branch_name::BranchName, disjoint_branch::DisjointBranchMap,
+    issue::IssuePatterns,
+};
+
+/// Trailer key naming an issue group a commit depends on.
+const DEPENDS_ON_TRAILER: &str = "Depends-On";
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DependencyError {
+    branch_name: BranchName,
+    kind: DependencyErrorKind,
+}
+
+impl Display for DependencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DependencyErrorKind::Unselected(dependency) => write!(
+                f,
+                "branch `{}` depends on {dependency:?}, which is not one of the selected issue groups",
+                self.branch_name
+            ),
+            DependencyErrorKind::Cycle(cycle) => {
+                write!(f, "branch `{}` depends on itself: ", self.branch_name)?;
+                for (i, branch_name) in cycle.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    write!(f, "`{branch_name}`")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DependencyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            DependencyErrorKind::Unselected(_) => None,
+            DependencyErrorKind::Cycle(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DependencyErrorKind {
+    /// A `Depends-On:` trailer names a group that is not being published
+    #[non_exhaustive]
+    Unselected(String),
+    /// Following dependencies leads back to where they started
+    #[non_exhaustive]
+    Cycle(Vec<BranchName>),
+}
+
+/// Every group named by the `Depends-On:` trailers of `commits`, in order of
+/// first appearance.
+fn declared<'a, 'repo: 'a>(
+    commits: impl IntoIterator<Item = &'a SquashedCommit<'repo>>,
+    issue_patterns: &IssuePatterns,
+) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    for commit in commits.into_iter().flat_map(SquashedCommit::commits) {
+        for trailer in issue_patterns.trailers(commit.message().unwrap_or_default()) {
+            if trailer.key != DEPENDS_ON_TRAILER {
+                continue;
+            }
+            for value in trailer.value.split(',').map(str::trim) {
+                if !value.is_empty() && !values.iter().any(|seen| seen == value) {
+                    values.push(value.to_owned());
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Find a cycle through `start`, following the branches each branch is
+/// built on or depends on.
+fn find_cycle(branch_map: &DisjointBranchMap, start: &BranchName) -> Option<Vec<BranchName>> {
+    fn visit(
+        branch_map: &DisjointBranchMap,
+        start: &BranchName,
+        path: &mut Vec<BranchName>,
+        visited: &mut HashSet<BranchName>,
+    ) -> bool {
+        let Some(branch) = path
+            .last()
+            .and_then(|branch_name| branch_map.find_by_branch_name(branch_name))
+        else {
+            return false;
+        };
+        for prerequisite in branch.prerequisites() {
+            if prerequisite == start {
+                path.push(prerequisite.clone());
+                return true;
+            }
+            if !visited.insert(prerequisite.clone()) {
+                continue;
+            }
+            path.push(prerequisite.clone());
+            if visit(branch_map, start, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = vec![start.clone()];
+    visit(branch_map, start, &mut path, &mut HashSet::new()).then_some(path)
+}
+
+/// Report the first branch that is built on or depends on itself, directly
+/// or through other branches.
+pub fn check_cycles(branch_map: &DisjointBranchMap) -> Result<(), DependencyError> {
+    for (_issue_group, branch) in branch_map.iter() {
+        if let Some(cycle) = find_cycle(branch_map, &branch.branch_name) {
+            return Err(DependencyError {
+                branch_name: branch.branch_name.clone(),
+                kind: DependencyErrorKind::Cycle(cycle),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Record the dependencies declared by each branch's commits, build each
+/// dependent branch on its first dependency unless it is already stacked,
+/// and order the branches so dependencies are created first.
+///
+/// Dependencies on groups that are not in `branch_map`, and cycles, are
+/// errors.
+pub fn apply<'repo>(
+    mut branch_map: DisjointBranchMap<'repo>,
+    issue_patterns: &IssuePatterns,
+) -> Result<DisjointBranchMap<'repo>, DependencyError> {
+    for index in 0..branch_map.len() {
+        let (issue_group, branch) = branch_map
+            .get_index(index)
+            .expect("index should be in bounds");
+        let mut depends_on = Vec::new();
+        for value in declared(&branch.commits, issue_patterns) {
+            // A group may mention itself, e.g. when every commit is squashed
+            // into one that carries the trailer
+            if issue_group.is_named_by(&value) {
+                continue;
+            }
+            let dependency = branch_map
+                .iter()
+                .find(|(other, _branch)| other.is_named_by(&value))
+                .map(|(_other, dependency)| dependency.branch_name.clone())
+                .ok_or_else(|| DependencyError {
+                    branch_name: branch.branch_name.clone(),
+                    kind: DependencyErrorKind::Unselected(value.clone()),
+                })?;
+            if !depends_on.contains(&dependency) {
+                depends_on.push(dependency);
+            }
+        }
+        if let Some(branch) = branch_map.get_index_mut(index) {
+            branch.depends_on = depends_on;
+        }
+    }
+
+    check_cycles(&branch_map)?;
+
+    for index in 0..branch_map.len() {
+        if let Some(branch) = branch_map.get_index_mut(index) {
+            if branch.stacked_on.is_none() {
+                branch.stacked_on = branch.depends_on.first().cloned();
+            }
+        }
+    }
+    branch_map.sort_stacks();
+
+    Ok(branch_map)
+}
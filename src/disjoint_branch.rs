@@ -1,16 +1,40 @@
-use std::{collections::HashSet, error::Error, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+};
 
 use git2::Commit;
 use indexmap::IndexMap;
 
-use crate::{branch_name::BranchName, issue_group::IssueGroup, issue_group_map::IssueGroupMap};
+use crate::{
+    autosquash::{self, SquashedCommit},
+    branch_name::BranchName,
+    branch_template::BranchNaming,
+    issue_group::IssueGroup,
+    issue_group_map::IssueGroupMap,
+};
 
 #[derive(Debug)]
 pub struct DisjointBranch<'repo> {
     // REFACTOR: make this private
     pub branch_name: BranchName,
     // REFACTOR: make this private
-    pub commits: Vec<Commit<'repo>>,
+    /// Commits to cherry-pick, with fixup commits folded into their target.
+    pub commits: Vec<SquashedCommit<'repo>>,
+    /// The branch this branch is built on, when it is part of a stack.
+    /// Unstacked branches are built on the base branch.
+    pub stacked_on: Option<BranchName>,
+    /// The branches this branch depends on, declared with `Depends-On:`
+    /// trailers.
+    pub depends_on: Vec<BranchName>,
+}
+
+impl DisjointBranch<'_> {
+    /// The branches that must be created before this branch.
+    pub fn prerequisites(&self) -> impl Iterator<Item = &BranchName> {
+        self.stacked_on.iter().chain(self.depends_on.iter())
+    }
 }
 
 #[derive(Debug)]
@@ -60,6 +84,98 @@ impl<'repo> DisjointBranchMap<'repo> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn get_index(&self, index: usize) -> Option<(&IssueGroup, &DisjointBranch<'repo>)> {
+        self.0.get_index(index)
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut DisjointBranch<'repo>> {
+        self.0
+            .get_index_mut(index)
+            .map(|(_issue_group, branch)| branch)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn find_by_branch_name(&self, branch_name: &BranchName) -> Option<&DisjointBranch<'repo>> {
+        self.0
+            .values()
+            .find(|branch| &branch.branch_name == branch_name)
+    }
+
+    /// The branches `branch` is stacked on, bottom of the stack first,
+    /// followed by `branch` itself.
+    pub fn stack<'a>(
+        &'a self,
+        branch: &'a DisjointBranch<'repo>,
+    ) -> Vec<&'a DisjointBranch<'repo>> {
+        let mut stack = vec![branch];
+        while let Some(parent) = stack
+            .last()
+            .and_then(|branch| branch.stacked_on.as_ref())
+            .and_then(|parent| self.find_by_branch_name(parent))
+        {
+            // A stack never loops back on itself, but guard against it anyway
+            if stack
+                .iter()
+                .any(|branch| branch.branch_name == parent.branch_name)
+            {
+                break;
+            }
+            stack.push(parent);
+        }
+        stack.reverse();
+        stack
+    }
+
+    /// Every commit to pick to build `branch` from the base, including the
+    /// commits of the branches it is stacked on.
+    pub fn stack_commits<'a>(
+        &'a self,
+        branch: &'a DisjointBranch<'repo>,
+    ) -> impl Iterator<Item = &'a Commit<'repo>> {
+        self.stack(branch)
+            .into_iter()
+            .flat_map(|branch| branch.commits.iter().flat_map(SquashedCommit::commits))
+    }
+
+    /// How many branches must be created before `branch`, following the
+    /// branches it is built on and depends on.
+    fn depth(&self, branch: &DisjointBranch<'repo>, visiting: &mut Vec<BranchName>) -> usize {
+        // Cycles are reported before branches are sorted, but guard against
+        // them anyway
+        if visiting.contains(&branch.branch_name) {
+            return 0;
+        }
+        visiting.push(branch.branch_name.clone());
+        let depth = branch
+            .prerequisites()
+            .filter_map(|prerequisite| self.find_by_branch_name(prerequisite))
+            .map(|prerequisite| self.depth(prerequisite, visiting) + 1)
+            .max()
+            .unwrap_or(0);
+        visiting.pop();
+        depth
+    }
+
+    /// Order the branches so every branch comes after the branches it is
+    /// built on and depends on, and can be built from them.
+    pub fn sort_stacks(&mut self) {
+        let depths: HashMap<BranchName, usize> = self
+            .0
+            .values()
+            .map(|branch| {
+                (
+                    branch.branch_name.clone(),
+                    self.depth(branch, &mut Vec::new()),
+                )
+            })
+            .collect();
+        self.0
+            .sort_by(|_, a, _, b| depths[&a.branch_name].cmp(&depths[&b.branch_name]));
+    }
 }
 
 impl<'repo> FromIterator<(IssueGroup, DisjointBranch<'repo>)> for DisjointBranchMap<'repo> {
@@ -81,13 +197,31 @@ impl<'repo> IntoIterator for DisjointBranchMap<'repo> {
 impl<'repo> TryFrom<IssueGroupMap<'repo>> for DisjointBranchMap<'repo> {
     type Error = FromIssueGroupMapError;
 
+    fn try_from(commits_by_issue_group: IssueGroupMap<'repo>) -> Result<Self, Self::Error> {
+        Self::try_from_issue_group_map(
+            commits_by_issue_group,
+            &HashMap::new(),
+            &BranchNaming::default(),
+        )
+    }
+}
+
+impl<'repo> DisjointBranchMap<'repo> {
     /// Plan out branch names to avoid collisions.
     ///
+    /// Each branch is named after its issue group's title in `titles`, if it
+    /// has one, or else after the summary of the group's first commit, as
+    /// `naming` directs.
+    ///
     /// This function does not take into account existing branch names in the local
     /// or remote repository. It only looks at branch names that git-disjoint is
     /// going to generate to make sure one invocation of git-disjoint won't try to
     /// create a branch with the same name twice.
-    fn try_from(commits_by_issue_group: IssueGroupMap<'repo>) -> Result<Self, Self::Error> {
+    pub fn try_from_issue_group_map(
+        commits_by_issue_group: IssueGroupMap<'repo>,
+        titles: &HashMap<IssueGroup, String>,
+        naming: &BranchNaming,
+    ) -> Result<Self, FromIssueGroupMapError> {
         let mut suffix: u32 = 0;
         let mut seen_branch_names = HashSet::new();
         commits_by_issue_group
@@ -96,19 +230,26 @@ impl<'repo> TryFrom<IssueGroupMap<'repo>> for DisjointBranchMap<'repo> {
                 // Grab the first summary to convert into a branch name.
                 // We only choose the first summary because we know each Vec is
                 // non-empty and the first element is convenient.
-                let summary = {
-                    let commit = &commits[0];
-                    commit.summary().ok_or_else(|| {
-                        FromIssueGroupMapErrorKind::InvalidUtf8(commit.id().to_string())
-                    })?
+                let summary = match titles.get(&issue_group) {
+                    Some(title) => title.as_str(),
+                    None => {
+                        let commit = &commits[0];
+                        commit.summary().ok_or_else(|| {
+                            FromIssueGroupMapErrorKind::InvalidUtf8(commit.id().to_string())
+                        })?
+                    }
                 };
-                let generated_branch_name = BranchName::from_issue_group(&issue_group, summary);
-                let mut proposed_branch_name = generated_branch_name.clone();
+                let mut proposed_branch_name =
+                    BranchName::from_issue_group(&issue_group, summary, naming);
 
                 while seen_branch_names.contains(&proposed_branch_name) {
                     suffix += 1;
-                    // OPTIMIZE: no need to call sanitize_git_ref here again
-                    proposed_branch_name = format!("{generated_branch_name}_{suffix}").into();
+                    proposed_branch_name = BranchName::from_issue_group_numbered(
+                        &issue_group,
+                        summary,
+                        naming,
+                        Some(suffix),
+                    );
                 }
 
                 seen_branch_names.insert(proposed_branch_name.clone());
@@ -117,7 +258,9 @@ impl<'repo> TryFrom<IssueGroupMap<'repo>> for DisjointBranchMap<'repo> {
                     issue_group,
                     DisjointBranch {
                         branch_name: proposed_branch_name,
-                        commits,
+                        commits: autosquash::fold(commits),
+                        stacked_on: None,
+                        depends_on: Vec::new(),
                     },
                 ))
             })
@@ -81,6 +81,7 @@ impl From<GetPullRequestMetadataErrorKind> for GetPullRequestMetadataError {
 
 pub fn interactive_get_pr_metadata<'repo>(
     root: &Path,
+    default_title: Option<String>,
     commits: impl IntoIterator<Item = impl Into<&'repo Commit<'repo>>>,
 ) -> Result<PullRequestMetadata, GetPullRequestMetadataError> {
     let editor = get_editor().ok_or(GetPullRequestMetadataErrorKind::AmbiguousEditor)?;
@@ -96,6 +97,7 @@ pub fn interactive_get_pr_metadata<'repo>(
             .into_iter()
             .map(Into::into)
             .collect::<PullRequestMessageTemplate>()
+            .with_title(default_title)
     )
     .map_err(GetPullRequestMetadataErrorKind::BufferWrite)?;
 
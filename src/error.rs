@@ -3,8 +3,9 @@
 use std::fmt::Display;
 
 use crate::{
-    default_branch, disjoint_branch, editor, execute, git2_repository, github_repository_metadata,
-    interact, issue_group_map, pull_request, pull_request_metadata, token,
+    config, default_branch, dependency, disjoint_branch, editor, execute, git2_repository,
+    github_repository_metadata, interact, issue_group_map, plan, pull_request,
+    pull_request_metadata, regroup, token,
 };
 
 #[derive(Debug)]
@@ -22,12 +23,16 @@ impl Display for Error {
                 write!(f, "unable to cherry-pick commit {:?}", commit)
             }
             ErrorKind::RepositoryMetadata(_) => write!(f, "unable to gather repository metadata"),
+            ErrorKind::Config(_) => write!(f, "unable to read git-disjoint configuration"),
             ErrorKind::DefaultBranch(_) => write!(f, "unable to query repository's default branch"),
             ErrorKind::BaseCommit(_) => write!(f, "unable to identify repository's base commit"),
             ErrorKind::WalkCommits(_) => write!(f, "unable to walk commits"),
             ErrorKind::IssueGroup(_) => write!(f, "unable to group commits by issue"),
             ErrorKind::SelectIssues(_) => write!(f, "unable to select issue groups"),
+            ErrorKind::Regroup(_) => write!(f, "unable to regroup commits"),
             ErrorKind::PlanBranches(_) => write!(f, "unable to plan commits onto branches"),
+            ErrorKind::Dependency(_) => write!(f, "unable to order branches by dependency"),
+            ErrorKind::Plan(err) => write!(f, "{err}"),
             ErrorKind::Git(_) => write!(f, "git operation failed"),
             ErrorKind::Execute(_) => write!(f, "command failed to execute"),
             ErrorKind::GetPullRequestMetadata(_) => {
@@ -37,6 +42,7 @@ impl Display for Error {
                 write!(f, "unable to parse pull request metadata")
             }
             ErrorKind::ResolveToken(err) => write!(f, "{err}"),
+            ErrorKind::ConfirmForcePush(_) => write!(f, "unable to confirm force push"),
             ErrorKind::PreValidation => {
                 write!(f, "pre-validation failed: cherry-pick conflicts detected")
             }
@@ -51,17 +57,22 @@ impl std::error::Error for Error {
             ErrorKind::WebBrowser(err) => Some(err),
             ErrorKind::CherryPick(err, _) => Some(err),
             ErrorKind::RepositoryMetadata(err) => Some(err),
+            ErrorKind::Config(err) => Some(err),
             ErrorKind::DefaultBranch(err) => Some(err),
             ErrorKind::BaseCommit(err) => Some(err),
             ErrorKind::WalkCommits(err) => Some(err),
             ErrorKind::IssueGroup(err) => Some(err),
             ErrorKind::SelectIssues(err) => Some(err),
+            ErrorKind::Regroup(err) => Some(err),
             ErrorKind::PlanBranches(err) => Some(err),
+            ErrorKind::Dependency(err) => Some(err),
+            ErrorKind::Plan(err) => err.source(),
             ErrorKind::Git(err) => Some(err),
             ErrorKind::Execute(err) => Some(err),
             ErrorKind::GetPullRequestMetadata(err) => Some(err),
             ErrorKind::ParsePullRequestMetadata(err) => Some(err),
             ErrorKind::ResolveToken(err) => err.source(),
+            ErrorKind::ConfirmForcePush(err) => Some(err),
             ErrorKind::PreValidation => None,
         }
     }
@@ -72,6 +83,8 @@ pub enum ErrorKind {
     #[non_exhaustive]
     RepositoryMetadata(github_repository_metadata::TryDefaultError),
     #[non_exhaustive]
+    Config(config::FromGitConfigError),
+    #[non_exhaustive]
     DefaultBranch(default_branch::TryDefaultError),
     #[non_exhaustive]
     BaseCommit(git2_repository::BaseCommitError),
@@ -82,8 +95,14 @@ pub enum ErrorKind {
     #[non_exhaustive]
     SelectIssues(interact::SelectIssuesError),
     #[non_exhaustive]
+    Regroup(regroup::RegroupError),
+    #[non_exhaustive]
     PlanBranches(disjoint_branch::FromIssueGroupMapError),
     #[non_exhaustive]
+    Dependency(dependency::DependencyError),
+    #[non_exhaustive]
+    Plan(plan::PlanError),
+    #[non_exhaustive]
     Git(git2::Error),
     #[non_exhaustive]
     Execute(execute::ExecuteError),
@@ -100,6 +119,8 @@ pub enum ErrorKind {
     #[non_exhaustive]
     ResolveToken(token::ResolveTokenError),
     #[non_exhaustive]
+    ConfirmForcePush(inquire::InquireError),
+    #[non_exhaustive]
     PreValidation,
 }
 
@@ -110,6 +131,12 @@ impl Error {
         }
     }
 
+    pub fn confirm_force_push(err: inquire::InquireError) -> Self {
+        Self {
+            kind: ErrorKind::ConfirmForcePush(err),
+        }
+    }
+
     pub fn pre_validation() -> Self {
         Self {
             kind: ErrorKind::PreValidation,
@@ -140,6 +167,14 @@ impl From<github_repository_metadata::TryDefaultError> for Error {
     }
 }
 
+impl From<config::FromGitConfigError> for Error {
+    fn from(err: config::FromGitConfigError) -> Self {
+        Self {
+            kind: ErrorKind::Config(err),
+        }
+    }
+}
+
 impl From<default_branch::TryDefaultError> for Error {
     fn from(err: default_branch::TryDefaultError) -> Self {
         Self {
@@ -180,6 +215,14 @@ impl From<interact::SelectIssuesError> for Error {
     }
 }
 
+impl From<regroup::RegroupError> for Error {
+    fn from(err: regroup::RegroupError) -> Self {
+        Self {
+            kind: ErrorKind::Regroup(err),
+        }
+    }
+}
+
 impl From<disjoint_branch::FromIssueGroupMapError> for Error {
     fn from(err: disjoint_branch::FromIssueGroupMapError) -> Self {
         Self {
@@ -188,6 +231,22 @@ impl From<disjoint_branch::FromIssueGroupMapError> for Error {
     }
 }
 
+impl From<dependency::DependencyError> for Error {
+    fn from(err: dependency::DependencyError) -> Self {
+        Self {
+            kind: ErrorKind::Dependency(err),
+        }
+    }
+}
+
+impl From<plan::PlanError> for Error {
+    fn from(err: plan::PlanError) -> Self {
+        Self {
+            kind: ErrorKind::Plan(err),
+        }
+    }
+}
+
 impl From<git2::Error> for Error {
     fn from(err: git2::Error) -> Self {
         Self {
@@ -224,7 +283,8 @@ impl From<pull_request::CreatePullRequestError> for Error {
     fn from(err: pull_request::CreatePullRequestError) -> Self {
         match &err.kind {
             pull_request::CreatePullRequestErrorKind::Http(_)
-            | pull_request::CreatePullRequestErrorKind::Parse(_) => Self {
+            | pull_request::CreatePullRequestErrorKind::Parse(_)
+            | pull_request::CreatePullRequestErrorKind::UpdateIssue(_) => Self {
                 kind: ErrorKind::CreatePullRequest(err),
             },
             pull_request::CreatePullRequestErrorKind::OpenBrowser(_) => Self {
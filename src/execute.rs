@@ -62,7 +62,16 @@ pub enum ExecuteErrorKind {
 }
 
 pub fn execute(command: &[&str], log_file: &LogFile) -> Result<(), ExecuteError> {
-    (|| -> Result<(), ExecuteErrorKind> {
+    run(command, log_file, Stdio::null()).map(|_stdout| ())
+}
+
+/// Like [`execute`], but return what the command writes to stdout.
+pub fn execute_output(command: &[&str], log_file: &LogFile) -> Result<String, ExecuteError> {
+    run(command, log_file, Stdio::piped())
+}
+
+fn run(command: &[&str], log_file: &LogFile, stdout: Stdio) -> Result<String, ExecuteError> {
+    (|| -> Result<String, ExecuteErrorKind> {
         let mut runner = Command::new(command[0]);
 
         let mut file = OpenOptions::new()
@@ -79,7 +88,7 @@ pub fn execute(command: &[&str], log_file: &LogFile) -> Result<(), ExecuteError>
         // It's not immediately obvious to me how we pass `command`
         // to a duct `cmd`, but I bet there's a way to separate
         // the head and the tail from our slice.
-        runner.stdout(Stdio::null());
+        runner.stdout(stdout);
         runner.stderr(file);
 
         for argument in command.iter().skip(1) {
@@ -87,7 +96,7 @@ pub fn execute(command: &[&str], log_file: &LogFile) -> Result<(), ExecuteError>
         }
 
         // Try to run the command
-        let status = runner.status().map_err(|err| {
+        let output = runner.output().map_err(|err| {
             ExecuteErrorKind::Exec(
                 err,
                 command
@@ -99,9 +108,9 @@ pub fn execute(command: &[&str], log_file: &LogFile) -> Result<(), ExecuteError>
         })?;
 
         // Return an Err if the exit status is non-zero
-        if !status.success() {
+        if !output.status.success() {
             return Err(ExecuteErrorKind::Child(
-                NonZeroExitStatus(status),
+                NonZeroExitStatus(output.status),
                 command
                     .iter()
                     .map(ToOwned::to_owned)
@@ -109,7 +118,7 @@ pub fn execute(command: &[&str], log_file: &LogFile) -> Result<(), ExecuteError>
                     .collect(),
             ));
         }
-        Ok(())
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
     })()
     .map_err(|kind| ExecuteError { kind })
 }
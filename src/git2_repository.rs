@@ -1,13 +1,42 @@
 use std::{
+    collections::{HashMap, HashSet},
     error::Error,
-    fmt::Display,
+    fmt::{Display, Write},
     ops::Deref,
     path::{Path, PathBuf},
 };
 
-use git2::{Commit, RepositoryState};
+use git2::{Commit, Oid, RepositoryState};
 
-use crate::default_branch::DefaultBranch;
+use crate::{
+    autosquash::SquashedCommit,
+    default_branch::DefaultBranch,
+    execute::{execute_output, ExecuteError},
+    issue::trailer::{self, DEFAULT_SEPARATORS},
+    log_file::LogFile,
+};
+
+/// Trailer key holding a commit's stable identity, which survives amending
+/// and rewording the commit.
+const CHANGE_ID_TRAILER: &str = "Change-Id";
+
+/// The `Change-Id` trailer of `commit`, if it has one.
+fn change_id(commit: &Commit) -> Option<String> {
+    trailer::parse(commit.message().unwrap_or_default(), DEFAULT_SEPARATORS)
+        .into_iter()
+        .find(|trailer| trailer.key == CHANGE_ID_TRAILER && !trailer.value.is_empty())
+        .map(|trailer| trailer.value)
+}
+
+/// Whether `a` and `b` have the same author and author date.
+fn same_author(a: &Commit, b: &Commit) -> bool {
+    let (a, b) = (a.author(), b.author());
+    a.name_bytes() == b.name_bytes() && a.email_bytes() == b.email_bytes() && a.when() == b.when()
+}
+
+/// Notes ref recording, for each commit git-disjoint picks onto a branch, the
+/// commit it was picked from.
+pub const SOURCE_NOTES_REF: &str = "refs/notes/disjoint";
 
 pub struct Repository(git2::Repository);
 
@@ -142,6 +171,7 @@ impl Display for WalkCommitsError {
             WalkCommitsErrorKind::Push(_) => write!(f, "git2::push_head error"),
             WalkCommitsErrorKind::Hide(_) => write!(f, "git2::hide error"),
             WalkCommitsErrorKind::SetSorting(_) => write!(f, "git2::set_sorting error"),
+            WalkCommitsErrorKind::PatchId(_) => write!(f, "unable to compute patch-id"),
         }
     }
 }
@@ -153,6 +183,7 @@ impl Error for WalkCommitsError {
             WalkCommitsErrorKind::Push(err) => Some(err),
             WalkCommitsErrorKind::Hide(err) => Some(err),
             WalkCommitsErrorKind::SetSorting(err) => Some(err),
+            WalkCommitsErrorKind::PatchId(err) => Some(err),
         }
     }
 }
@@ -167,6 +198,8 @@ pub enum WalkCommitsErrorKind {
     Hide(git2::Error),
     #[non_exhaustive]
     SetSorting(git2::Error),
+    #[non_exhaustive]
+    PatchId(git2::Error),
 }
 
 impl Repository {
@@ -228,10 +261,248 @@ impl Repository {
 
     /// Identify commits by topologically traversing commits starting from HEAD
     /// and working towards base (no parents before all its children are shown).
+    ///
+    /// Like `git cherry`, commits whose changes are already in the history of
+    /// `base`, such as the commits of a PR that was rebase-merged, are set
+    /// aside. Returns the commits to consider and the commits already upstream.
     pub fn commits_since_base<'repo>(
         &'repo self,
         base: &'repo Commit,
-    ) -> Result<impl Iterator<Item = Commit<'repo>>, WalkCommitsError> {
+    ) -> Result<(Vec<Commit<'repo>>, Vec<Commit<'repo>>), WalkCommitsError> {
+        (|| {
+            let head = self
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .map_err(WalkCommitsErrorKind::Push)?
+                .id();
+            let commits = self.walk_commits(head, base.id())?;
+            if commits.is_empty() {
+                return Ok((commits, Vec::new()));
+            }
+
+            // Only commits on `base` that are not on HEAD can hold the same
+            // changes as a commit on HEAD. Merges have no single patch to
+            // compare, as `git cherry` ignores them.
+            let upstream_patch_ids = self
+                .walk_commits(base.id(), head)?
+                .iter()
+                .filter(|commit| commit.parent_count() <= 1)
+                .map(|commit| self.patch_id(commit))
+                .collect::<Result<HashSet<Oid>, _>>()
+                .map_err(WalkCommitsErrorKind::PatchId)?;
+            if upstream_patch_ids.is_empty() {
+                return Ok((commits, Vec::new()));
+            }
+
+            let mut unpublished = Vec::new();
+            let mut upstream = Vec::new();
+            for commit in commits {
+                let patch_id = self
+                    .patch_id(&commit)
+                    .map_err(WalkCommitsErrorKind::PatchId)?;
+                match upstream_patch_ids.contains(&patch_id) {
+                    true => upstream.push(commit),
+                    false => unpublished.push(commit),
+                }
+            }
+            Ok((unpublished, upstream))
+        })()
+        .map_err(|kind| WalkCommitsError { kind })
+    }
+
+    /// The planned `commits` that `branch` does not hold yet, compared by
+    /// patch-id, ignoring the history of `base`.
+    ///
+    /// A commit with fixups is on the branch when the branch holds it either
+    /// on its own or squashed with its fixups.
+    pub fn missing_commits<'a, 'repo: 'a>(
+        &self,
+        branch: &Commit,
+        base: &Commit,
+        commits: impl IntoIterator<Item = &'a SquashedCommit<'repo>>,
+    ) -> Result<HashSet<Oid>, WalkCommitsError> {
+        (|| {
+            let branch_patch_ids = self
+                .walk_commits(branch.id(), base.id())?
+                .iter()
+                .filter(|commit| commit.parent_count() <= 1)
+                .map(|commit| self.patch_id(commit))
+                .collect::<Result<HashSet<Oid>, _>>()
+                .map_err(WalkCommitsErrorKind::PatchId)?;
+            let mut missing = HashSet::new();
+            for commit in commits {
+                let patch_ids = self
+                    .squashed_patch_ids(commit)
+                    .map_err(WalkCommitsErrorKind::PatchId)?;
+                if !patch_ids
+                    .iter()
+                    .any(|patch_id| branch_patch_ids.contains(patch_id))
+                {
+                    missing.insert(commit.commit.id());
+                }
+            }
+            Ok(missing)
+        })()
+        .map_err(|kind| WalkCommitsError { kind })
+    }
+
+    /// The planned `commits` of which `branch` holds an outdated version,
+    /// ignoring the history of `base`.
+    ///
+    /// A branch commit is a version of a planned commit when both have the
+    /// same `Change-Id` trailer. Failing that, it is one when it was picked
+    /// from the planned commit, as recorded under [`SOURCE_NOTES_REF`], or
+    /// from a commit no longer planned with the same author and author date,
+    /// which amending and rebasing keep. It is outdated when its changes or
+    /// its message differ, as after the planned commit was amended or
+    /// reworded.
+    pub fn outdated_commits<'a, 'repo: 'a>(
+        &self,
+        branch: &Commit,
+        base: &Commit,
+        commits: impl IntoIterator<Item = &'a SquashedCommit<'repo>>,
+    ) -> Result<HashSet<Oid>, WalkCommitsError> {
+        (|| {
+            let commits: Vec<&SquashedCommit> = commits.into_iter().collect();
+            let planned: HashSet<Oid> = commits.iter().map(|commit| commit.commit.id()).collect();
+            let branch_commits = self.walk_commits(branch.id(), base.id())?;
+            let sources: Vec<Option<Commit>> = branch_commits
+                .iter()
+                .map(|published| self.source_commit(published))
+                .collect();
+
+            // Match by identity first, so the author fallback only pairs the
+            // branch commits left over, each with one planned commit
+            let mut versions: HashMap<Oid, usize> = HashMap::new();
+            for commit in &commits {
+                let identity = change_id(&commit.commit);
+                let version =
+                    branch_commits
+                        .iter()
+                        .zip(&sources)
+                        .position(|(published, source)| match &identity {
+                            Some(identity) => change_id(published).as_ref() == Some(identity),
+                            None => source
+                                .as_ref()
+                                .is_some_and(|source| source.id() == commit.commit.id()),
+                        });
+                if let Some(version) = version {
+                    versions.insert(commit.commit.id(), version);
+                }
+            }
+            for commit in &commits {
+                if versions.contains_key(&commit.commit.id()) || change_id(&commit.commit).is_some()
+                {
+                    continue;
+                }
+                let version = sources.iter().enumerate().position(|(index, source)| {
+                    !versions.values().any(|version| *version == index)
+                        && source.as_ref().is_some_and(|source| {
+                            !planned.contains(&source.id()) && same_author(source, &commit.commit)
+                        })
+                });
+                if let Some(version) = version {
+                    versions.insert(commit.commit.id(), version);
+                }
+            }
+
+            let mut outdated = HashSet::new();
+            for commit in commits {
+                let Some(published) = versions
+                    .get(&commit.commit.id())
+                    .map(|version| &branch_commits[*version])
+                else {
+                    continue;
+                };
+                let patch_ids = self
+                    .squashed_patch_ids(commit)
+                    .map_err(WalkCommitsErrorKind::PatchId)?;
+                let patch_id = self
+                    .patch_id(published)
+                    .map_err(WalkCommitsErrorKind::PatchId)?;
+                let reworded =
+                    published.message().unwrap_or_default().trim() != commit.message().trim();
+                if reworded || !patch_ids.contains(&patch_id) {
+                    outdated.insert(commit.commit.id());
+                }
+            }
+            Ok(outdated)
+        })()
+        .map_err(|kind| WalkCommitsError { kind })
+    }
+
+    /// The commit `published` was picked from, if git-disjoint recorded it.
+    fn source_commit(&self, published: &Commit) -> Option<Commit<'_>> {
+        let note = self
+            .find_note(Some(SOURCE_NOTES_REF), published.id())
+            .ok()?;
+        let source = Oid::from_str(note.message()?.trim()).ok()?;
+        self.find_commit(source).ok()
+    }
+
+    /// Render how a branch differs between two versions of it with
+    /// `git range-diff`, commit by commit and message included, each version
+    /// taken relative to its own base.
+    pub fn render_range_diff(
+        &self,
+        (old_base, old): (&Commit, &Commit),
+        (new_base, new): (&Commit, &Commit),
+        log_file: &LogFile,
+    ) -> Result<String, ExecuteError> {
+        let git_dir = format!("--git-dir={}", self.path().display());
+        let old_range = format!("{}..{}", old_base.id(), old.id());
+        let new_range = format!("{}..{}", new_base.id(), new.id());
+        execute_output(
+            &[
+                "git",
+                &git_dir,
+                "range-diff",
+                "--no-color",
+                &old_range,
+                &new_range,
+            ],
+            log_file,
+        )
+    }
+
+    /// The patch-ids a branch may hold `commit` as: the patch-id of the commit
+    /// on its own, and of the commit squashed with its fixups.
+    fn squashed_patch_ids(&self, commit: &SquashedCommit) -> Result<Vec<Oid>, git2::Error> {
+        let mut patch_ids = vec![self.patch_id(&commit.commit)?];
+        if commit.fixups.is_empty() {
+            return Ok(patch_ids);
+        }
+
+        // Apply each fixup to the commit's tree, as the autosquash does
+        let mut tree = commit.commit.tree()?;
+        for fixup in &commit.fixups {
+            let ancestor = match fixup.commit.parent_count() {
+                0 => self.find_tree(self.treebuilder(None)?.write()?)?,
+                _ => fixup.commit.parent(0)?.tree()?,
+            };
+            let mut index = self.merge_trees(&ancestor, &tree, &fixup.commit.tree()?, None)?;
+            if index.has_conflicts() {
+                return Ok(patch_ids);
+            }
+            tree = self.find_tree(index.write_tree_to(self)?)?;
+        }
+        let parent_tree = match commit.commit.parent_count() {
+            0 => None,
+            _ => Some(commit.commit.parent(0)?.tree()?),
+        };
+        patch_ids.push(
+            self.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?
+                .patchid(None)?,
+        );
+        Ok(patch_ids)
+    }
+
+    /// The commits reachable from `from` but not from `hidden`, oldest first.
+    fn walk_commits(
+        &self,
+        from: Oid,
+        hidden: Oid,
+    ) -> Result<Vec<Commit<'_>>, WalkCommitsErrorKind> {
         macro_rules! filter_try {
             ($e:expr) => {
                 match $e {
@@ -241,34 +512,81 @@ impl Repository {
             };
         }
 
-        let revwalk = (|| {
-            let mut revwalk = self.revwalk().map_err(WalkCommitsErrorKind::Revwalk)?;
+        let mut revwalk = self.revwalk().map_err(WalkCommitsErrorKind::Revwalk)?;
 
-            // Starting from HEAD
-            revwalk.push_head().map_err(WalkCommitsErrorKind::Push)?;
+        // Starting from `from`
+        revwalk.push(from).map_err(WalkCommitsErrorKind::Push)?;
 
-            // ignore the base branch and all of its ancestors
-            revwalk
-                .hide(base.id())
-                .map_err(WalkCommitsErrorKind::Hide)?;
+        // ignore `hidden` and all of its ancestors
+        revwalk.hide(hidden).map_err(WalkCommitsErrorKind::Hide)?;
 
-            // then reverse the ordering
-            revwalk
-                .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)
-                .map_err(WalkCommitsErrorKind::SetSorting)?;
+        // then reverse the ordering
+        revwalk
+            .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)
+            .map_err(WalkCommitsErrorKind::SetSorting)?;
 
-            Ok(revwalk)
-        })()
-        .map_err(|kind| WalkCommitsError { kind })?;
+        Ok(revwalk
+            .filter_map(|id| {
+                // FIXME: do not silently drop errors
+                let id = filter_try!(id);
+                let commit = filter_try!(self.find_commit(id));
+                Some(commit)
+            })
+            .collect())
+    }
 
-        let iter = revwalk.filter_map(|id| {
-            // FIXME: do not silently drop errors
-            let id = filter_try!(id);
-            let commit = filter_try!(self.find_commit(id));
-            Some(commit)
-        });
+    /// The patch-id of the changes `commit` makes, which is the same for any
+    /// commit making the same changes, like `git patch-id`.
+    fn patch_id(&self, commit: &Commit) -> Result<Oid, git2::Error> {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        self.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?
+            .patchid(None)
+    }
+}
+
+/// List the commits that were skipped because their changes are already
+/// upstream, oldest first.
+pub fn render_already_upstream(upstream: &[Commit]) -> String {
+    let mut output = String::new();
+    if upstream.is_empty() {
+        return output;
+    }
+    writeln!(output, "skipped (already upstream):").unwrap();
+    for commit in upstream {
+        writeln!(output, "  * {}", commit.summary().unwrap_or_default()).unwrap();
+    }
+    output
+}
+
+/// The position of each commit to publish in history, oldest first, as
+/// walked by [`Repository::commits_since_base`].
+///
+/// Unlike comparing ancestry, this orders every pair of commits, even commits
+/// on different sides of a merge.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryOrder(HashMap<Oid, usize>);
+
+impl HistoryOrder {
+    /// Sort key placing `commit` in history. Commits outside the history
+    /// sort after it, by id.
+    pub fn key(&self, commit: &Commit) -> (usize, Oid) {
+        let position = self.0.get(&commit.id()).copied().unwrap_or(usize::MAX);
+        (position, commit.id())
+    }
+}
 
-        Ok(iter)
+impl<'a, 'repo: 'a> FromIterator<&'a Commit<'repo>> for HistoryOrder {
+    fn from_iter<I: IntoIterator<Item = &'a Commit<'repo>>>(commits: I) -> Self {
+        Self(
+            commits
+                .into_iter()
+                .enumerate()
+                .map(|(position, commit)| (commit.id(), position))
+                .collect(),
+        )
     }
 }
 
@@ -0,0 +1,152 @@
+use std::{error::Error, fmt::Display};
+
+use serde::Deserialize;
+
+/// The parts of a GitHub issue that carry over to the pull request closing it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GithubIssue {
+    pub title: String,
+    pub labels: Vec<String>,
+    /// The milestone's number, which is only meaningful in the issue's
+    /// repository.
+    pub milestone: Option<u64>,
+}
+
+// https://docs.github.com/en/rest/issues/issues?apiVersion=2022-11-28#get-an-issue
+#[derive(Debug, Deserialize)]
+struct GetIssueResponse {
+    title: String,
+    #[serde(default)]
+    labels: Vec<Label>,
+    milestone: Option<Milestone>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Milestone {
+    number: u64,
+}
+
+impl From<GetIssueResponse> for GithubIssue {
+    fn from(response: GetIssueResponse) -> Self {
+        Self {
+            title: response.title,
+            labels: response
+                .labels
+                .into_iter()
+                .map(|label| label.name)
+                .collect(),
+            milestone: response.milestone.map(|milestone| milestone.number),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct GetGithubIssueError {
+    url: String,
+    kind: GetGithubIssueErrorKind,
+}
+
+impl Display for GetGithubIssueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            GetGithubIssueErrorKind::Http(_) => write!(f, "http error: GET {}", self.url),
+            GetGithubIssueErrorKind::Parse(_) => {
+                write!(f, "unable to parse response from GET {}", self.url)
+            }
+        }
+    }
+}
+
+impl Error for GetGithubIssueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            GetGithubIssueErrorKind::Http(err) => Some(err),
+            GetGithubIssueErrorKind::Parse(err) => Some(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GetGithubIssueErrorKind {
+    #[non_exhaustive]
+    Http(reqwest::Error),
+    #[non_exhaustive]
+    Parse(reqwest::Error),
+}
+
+impl GithubIssue {
+    pub fn get(
+        http_client: &reqwest::blocking::Client,
+        owner: &str,
+        name: &str,
+        number: &str,
+        github_token: &str,
+    ) -> Result<Self, GetGithubIssueError> {
+        let url = format!("https://api.github.com/repos/{owner}/{name}/issues/{number}");
+        let response: GetIssueResponse = http_client
+            .get(&url)
+            .header("User-Agent", "git-disjoint")
+            .header("Accept", "application/vnd.github+json")
+            .header("Authorization", format!("token {github_token}"))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| GetGithubIssueError {
+                url: url.clone(),
+                kind: GetGithubIssueErrorKind::Http(err),
+            })?
+            .json()
+            .map_err(|err| GetGithubIssueError {
+                url,
+                kind: GetGithubIssueErrorKind::Parse(err),
+            })?;
+
+        Ok(response.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GetIssueResponse, GithubIssue};
+
+    #[test]
+    fn parse_issue_with_labels_and_milestone() {
+        let response: GetIssueResponse = serde_json::from_str(
+            r#"{
+                "number": 42,
+                "title": "Parser crashes on empty input",
+                "labels": [{ "id": 1, "name": "bug" }, { "id": 2, "name": "parser" }],
+                "milestone": { "number": 3, "title": "v1.0" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            GithubIssue::from(response),
+            GithubIssue {
+                title: "Parser crashes on empty input".to_string(),
+                labels: vec!["bug".to_string(), "parser".to_string()],
+                milestone: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_issue_without_labels_or_milestone() {
+        let response: GetIssueResponse =
+            serde_json::from_str(r#"{ "title": "Add docs", "labels": [], "milestone": null }"#)
+                .unwrap();
+        assert_eq!(
+            GithubIssue::from(response),
+            GithubIssue {
+                title: "Add docs".to_string(),
+                labels: Vec::new(),
+                milestone: None,
+            }
+        );
+    }
+}
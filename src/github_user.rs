@@ -0,0 +1,105 @@
+use std::{error::Error, fmt::Display};
+
+use serde::Deserialize;
+
+/// The GitHub user owning a token, whose login fills `{user}` in branch
+/// name templates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GithubUser {
+    pub login: String,
+}
+
+// https://docs.github.com/en/rest/users/users?apiVersion=2022-11-28#get-the-authenticated-user
+#[derive(Debug, Deserialize)]
+struct GetUserResponse {
+    login: String,
+}
+
+impl From<GetUserResponse> for GithubUser {
+    fn from(response: GetUserResponse) -> Self {
+        Self {
+            login: response.login,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct GetGithubUserError {
+    url: String,
+    kind: GetGithubUserErrorKind,
+}
+
+impl Display for GetGithubUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            GetGithubUserErrorKind::Http(_) => write!(f, "http error: GET {}", self.url),
+            GetGithubUserErrorKind::Parse(_) => {
+                write!(f, "unable to parse response from GET {}", self.url)
+            }
+        }
+    }
+}
+
+impl Error for GetGithubUserError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            GetGithubUserErrorKind::Http(err) => Some(err),
+            GetGithubUserErrorKind::Parse(err) => Some(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GetGithubUserErrorKind {
+    #[non_exhaustive]
+    Http(reqwest::Error),
+    #[non_exhaustive]
+    Parse(reqwest::Error),
+}
+
+impl GithubUser {
+    pub fn get(
+        http_client: &reqwest::blocking::Client,
+        github_token: &str,
+    ) -> Result<Self, GetGithubUserError> {
+        let url = "https://api.github.com/user".to_string();
+        let response: GetUserResponse = http_client
+            .get(&url)
+            .header("User-Agent", "git-disjoint")
+            .header("Accept", "application/vnd.github+json")
+            .header("Authorization", format!("token {github_token}"))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| GetGithubUserError {
+                url: url.clone(),
+                kind: GetGithubUserErrorKind::Http(err),
+            })?
+            .json()
+            .map_err(|err| GetGithubUserError {
+                url,
+                kind: GetGithubUserErrorKind::Parse(err),
+            })?;
+
+        Ok(response.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GetUserResponse, GithubUser};
+
+    #[test]
+    fn parse_authenticated_user() {
+        let response: GetUserResponse = serde_json::from_str(
+            r#"{ "login": "octocat", "id": 1, "name": "The Octocat", "type": "User" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            GithubUser::from(response),
+            GithubUser {
+                login: "octocat".to_string()
+            }
+        );
+    }
+}
@@ -1,12 +1,13 @@
 use std::{collections::HashSet, error::Error, fmt::Display};
 
-use inquire::{formatter::MultiOptionFormatter, MultiSelect};
+use git2::{Commit, Oid};
+use inquire::{formatter::MultiOptionFormatter, Confirm, MultiSelect};
 
-use crate::issue_group::IssueGroup;
+use crate::{autosquash::SquashedCommit, branch_name::BranchName, issue_group::IssueGroup};
 
 #[derive(Debug)]
-pub enum IssueGroupWhitelist {
-    Whitelist(HashSet<IssueGroup>),
+pub enum CommitWhitelist {
+    Whitelist(HashSet<Oid>),
     WhitelistDNE,
 }
 
@@ -20,6 +21,16 @@ impl Display for SelectIssuesError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.kind {
             SelectIssuesErrorKind::Prompt(_) => write!(f, "unable to process issue selection"),
+            SelectIssuesErrorKind::Diff(_) => write!(f, "unable to compute commit diffstat"),
+            SelectIssuesErrorKind::UnknownIssue(issue) => {
+                write!(f, "no commit to publish belongs to issue {issue:?}")
+            }
+            SelectIssuesErrorKind::UnknownCommit(rev) => {
+                write!(f, "commit {rev:?} is not one of the commits to publish")
+            }
+            SelectIssuesErrorKind::CombinedTwice(issue) => {
+                write!(f, "issue {issue:?} is combined more than once")
+            }
         }
     }
 }
@@ -28,6 +39,10 @@ impl Error for SelectIssuesError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self.kind {
             SelectIssuesErrorKind::Prompt(err) => Some(err),
+            SelectIssuesErrorKind::Diff(err) => Some(err),
+            SelectIssuesErrorKind::UnknownIssue(_) => None,
+            SelectIssuesErrorKind::UnknownCommit(_) => None,
+            SelectIssuesErrorKind::CombinedTwice(_) => None,
         }
     }
 }
@@ -36,6 +51,24 @@ impl Error for SelectIssuesError {
 pub enum SelectIssuesErrorKind {
     #[non_exhaustive]
     Prompt(inquire::InquireError),
+    #[non_exhaustive]
+    Diff(git2::Error),
+    /// No issue group is named by an `--issue`, `--exclude-issue` or
+    /// `--combine` value
+    #[non_exhaustive]
+    UnknownIssue(String),
+    /// A `--commit` value is not one of the commits to publish
+    #[non_exhaustive]
+    UnknownCommit(String),
+    /// A `--combine` value names a group that is already combined
+    #[non_exhaustive]
+    CombinedTwice(String),
+}
+
+impl From<SelectIssuesErrorKind> for SelectIssuesError {
+    fn from(kind: SelectIssuesErrorKind) -> Self {
+        Self { kind }
+    }
 }
 
 impl From<inquire::InquireError> for SelectIssuesError {
@@ -46,19 +79,137 @@ impl From<inquire::InquireError> for SelectIssuesError {
     }
 }
 
-// TODO: refactor into a better abstraction -- this seems coupled with IssueGroupMap
-pub fn prompt_user<'a, I: Iterator<Item = &'a IssueGroup>>(
-    choices: I,
-) -> Result<HashSet<IssueGroup>, SelectIssuesError> {
-    let formatter: MultiOptionFormatter<&IssueGroup> =
-        &|selected| format!("Selected: {selected:?}");
-
-    Ok(
-        MultiSelect::new("Select the issues to create PRs for:", choices.collect())
-            .with_formatter(formatter)
-            .prompt()?
-            .into_iter()
-            .cloned()
-            .collect(),
+impl From<git2::Error> for SelectIssuesError {
+    fn from(err: git2::Error) -> Self {
+        Self {
+            kind: SelectIssuesErrorKind::Diff(err),
+        }
+    }
+}
+
+/// One commit in the selection menu, with its fixup commits.
+///
+/// Each choice names its issue group, so typing an issue key filters the menu
+/// down to that group's commits, and its short OID, so commits can be found by
+/// hash.
+#[derive(Debug)]
+pub struct CommitChoice<'a> {
+    issue_group: &'a IssueGroup,
+    commit: &'a Commit<'a>,
+    /// The commit and its fixup commits.
+    oids: Vec<Oid>,
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl<'a> CommitChoice<'a> {
+    pub fn new(
+        issue_group: &'a IssueGroup,
+        squashed: &'a SquashedCommit<'a>,
+        repo: &git2::Repository,
+    ) -> Result<Self, git2::Error> {
+        let commit = &squashed.commit;
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let stats = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?
+            .stats()?;
+        Ok(Self {
+            issue_group,
+            commit,
+            oids: squashed.commits().map(Commit::id).collect(),
+            files_changed: stats.files_changed(),
+            insertions: stats.insertions(),
+            deletions: stats.deletions(),
+        })
+    }
+}
+
+impl Display for CommitChoice<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let files = match self.files_changed {
+            1 => "file",
+            _ => "files",
+        };
+        write!(
+            f,
+            "{} · {:.7} · {} · {} · {} {files}, +{} -{}",
+            self.issue_group,
+            self.commit.id(),
+            self.commit.author().name().unwrap_or("unknown"),
+            self.commit.summary().unwrap_or_default(),
+            self.files_changed,
+            self.insertions,
+            self.deletions,
+        )?;
+        if self.oids.len() > 1 {
+            let fixups = self.oids.len() - 1;
+            write!(
+                f,
+                " (with {fixups} fixup{})",
+                if fixups == 1 { "" } else { "s" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prompt the user to select commits, returning the OIDs of every selected
+/// commit and its fixup commits.
+pub fn prompt_user(choices: Vec<CommitChoice>) -> Result<HashSet<Oid>, SelectIssuesError> {
+    let formatter: MultiOptionFormatter<CommitChoice> =
+        &|selected| format!("Selected: {} commits", selected.len());
+
+    Ok(MultiSelect::new(
+        "Select the commits to create PRs for (type an issue or hash to filter, → to select all shown):",
+        choices,
     )
+    .with_formatter(formatter)
+    .prompt()?
+    .into_iter()
+    .flat_map(|choice| choice.oids)
+    .collect())
+}
+
+/// Ask whether to force-push the rebuilt `branch` over its published version.
+pub fn confirm_force_push(branch: &BranchName) -> Result<bool, inquire::InquireError> {
+    Confirm::new(&format!("Force-push the rebuilt branch {branch}?"))
+        .with_default(false)
+        .with_help_message("pass --yes to push rebuilt branches without asking")
+        .prompt()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::CommitChoice;
+    use crate::{autosquash::SquashedCommit, issue::Issue, issue_group::IssueGroup};
+
+    #[test]
+    fn choice_shows_group_short_oid_author_and_diffstat() {
+        let tempdir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(tempdir.path()).unwrap();
+        let signature = git2::Signature::now("Alice", "alice@test.com").unwrap();
+        std::fs::write(tempdir.path().join("a"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let oid = repo
+            .commit(None, &signature, &signature, "feat: add a", &tree, &[])
+            .unwrap();
+
+        let issue_group = IssueGroup::Issue(Issue::WorkTracker("AB-1".to_string()));
+        let squashed = SquashedCommit::from(repo.find_commit(oid).unwrap());
+        let choice = CommitChoice::new(&issue_group, &squashed, &repo).unwrap();
+        assert_eq!(
+            choice.to_string(),
+            format!("Issue AB-1 · {oid:.7} · Alice · feat: add a · 1 file, +2 -0")
+        );
+    }
 }
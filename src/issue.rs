@@ -1,12 +1,31 @@
+use std::error::Error;
 use std::fmt::Display;
-use std::sync::OnceLock;
+use std::str::FromStr;
+
+use regex::{Regex, RegexBuilder};
 
 macro_rules! regex {
     ($re:literal $(,)?) => {{
-        static RE: OnceLock<regex::Regex> = OnceLock::new();
+        static RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
         RE.get_or_init(|| regex::Regex::new($re).unwrap())
     }};
 }
+pub(crate) use regex;
+
+pub mod trailer;
+
+/// Name of the capture group that holds the issue identifier in an
+/// [`IssuePattern`].
+const ISSUE_IDENTIFIER_CAPTURE: &str = "id";
+
+/// Names of the optional capture groups that hold the owner and name of the
+/// repository a GitHub issue lives in.
+const GITHUB_OWNER_CAPTURE: &str = "owner";
+const GITHUB_REPOSITORY_CAPTURE: &str = "repo";
+
+/// Name of the optional capture group that marks a reference as closing its
+/// issue, e.g. the `Closes` in `Closes #123`.
+const CLOSING_CAPTURE: &str = "closes";
 
 /// WorkTracker or GitHub issue identifier.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -14,45 +33,466 @@ macro_rules! regex {
 pub enum Issue {
     WorkTracker(String),
     GitHub(String),
+    /// A GitHub issue in another repository, e.g. `org/other-repo#123`.
+    GitHubCrossRepository {
+        owner: String,
+        repository: String,
+        number: String,
+    },
 }
 
-impl Issue {
-    pub fn parse_from_commit_message<S: AsRef<str>>(commit_message: S) -> Option<Issue> {
-        let regex_issue = regex!(r"(?m)^(?:Closes )?Ticket:\s+(\S+)");
-        if let Some(issue_captures) = regex_issue.captures(commit_message.as_ref()) {
-            return Some(Issue::WorkTracker(
-                issue_captures[issue_captures.len() - 1].to_owned(),
-            ));
+/// Whether a commit resolves an issue, or only relates to it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum IssueIntent {
+    References,
+    Closes,
+}
+
+impl IssueIntent {
+    /// The keyword introducing a reference with this intent in a PR footer.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            IssueIntent::Closes => "Closes",
+            IssueIntent::References => "Refs",
+        }
+    }
+}
+
+/// An issue, as referenced from a commit message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IssueReference {
+    pub issue: Issue,
+    pub intent: IssueIntent,
+}
+
+/// Renders the reference as a PR footer line, e.g. `Closes #12` or
+/// `Refs ABC-3`.
+impl Display for IssueReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.intent.keyword(), self.issue.reference())
+    }
+}
+
+/// Add `reference` to `references`, keeping the strongest intent when the
+/// issue is already there.
+pub fn merge_reference(references: &mut Vec<IssueReference>, reference: IssueReference) {
+    match references
+        .iter_mut()
+        .find(|existing| existing.issue == reference.issue)
+    {
+        Some(existing) => existing.intent = existing.intent.max(reference.intent),
+        None => references.push(reference),
+    }
+}
+
+/// The kind of issue tracker an [`IssuePattern`] refers to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IssueKind {
+    WorkTracker,
+    GitHub,
+}
+
+impl IssueKind {
+    fn issue(self, captures: &regex::Captures) -> Option<Issue> {
+        let identifier = captures.name(ISSUE_IDENTIFIER_CAPTURE)?.as_str().to_owned();
+        Some(match self {
+            IssueKind::WorkTracker => Issue::WorkTracker(normalize_issue_key(identifier)),
+            IssueKind::GitHub => match (
+                captures.name(GITHUB_OWNER_CAPTURE),
+                captures.name(GITHUB_REPOSITORY_CAPTURE),
+            ) {
+                (Some(owner), Some(repository)) => Issue::GitHubCrossRepository {
+                    owner: owner.as_str().to_owned(),
+                    repository: repository.as_str().to_owned(),
+                    number: identifier,
+                },
+                _ => Issue::GitHub(identifier),
+            },
+        })
+    }
+}
+
+/// Upper-case issue keys like `ab-123`, so they group with `AB-123`.
+///
+/// Identifiers that do not look like an issue key are left untouched.
+fn normalize_issue_key(identifier: String) -> String {
+    match regex!(r"^[A-Za-z][A-Za-z0-9_]*-\d+$").is_match(&identifier) {
+        true => identifier.to_uppercase(),
+        false => identifier,
+    }
+}
+
+impl FromStr for IssueKind {
+    type Err = ParseIssuePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ticket" | "work-tracker" | "worktracker" => Ok(IssueKind::WorkTracker),
+            "github" => Ok(IssueKind::GitHub),
+            _ => Err(ParseIssuePatternError {
+                pattern: s.to_owned(),
+                kind: ParseIssuePatternErrorKind::UnknownIssueKind,
+            }),
+        }
+    }
+}
+
+/// A regular expression that recognizes an issue reference in a commit message.
+///
+/// The regex must contain a named capture group `id` holding the issue identifier.
+/// GitHub patterns may also capture `owner` and `repo` to reference an issue in
+/// another repository.
+#[derive(Clone, Debug)]
+pub struct IssuePattern {
+    kind: IssueKind,
+    scope: PatternScope,
+    regex: Regex,
+}
+
+/// The part of a commit message an [`IssuePattern`] is matched against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum PatternScope {
+    /// The commit's trailers, one normalized `Key: value` line per trailer.
+    Trailers,
+    /// The entire commit message, the way GitHub scans for closing keywords.
+    Message,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ParseIssuePatternError {
+    pattern: String,
+    kind: ParseIssuePatternErrorKind,
+}
+
+impl Display for ParseIssuePatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseIssuePatternErrorKind::MissingIssueKind => write!(
+                f,
+                "expected issue pattern of the form <kind>:<regex>, got {:?}",
+                self.pattern
+            ),
+            ParseIssuePatternErrorKind::UnknownIssueKind => write!(
+                f,
+                "unknown issue kind {:?}, expected one of: ticket, github",
+                self.pattern
+            ),
+            ParseIssuePatternErrorKind::Regex(_) => {
+                write!(f, "invalid regex in issue pattern {:?}", self.pattern)
+            }
+            ParseIssuePatternErrorKind::MissingIdentifierCapture => write!(
+                f,
+                "issue pattern {:?} has no named capture group `{ISSUE_IDENTIFIER_CAPTURE}`",
+                self.pattern
+            ),
+        }
+    }
+}
+
+impl Error for ParseIssuePatternError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseIssuePatternErrorKind::MissingIssueKind => None,
+            ParseIssuePatternErrorKind::UnknownIssueKind => None,
+            ParseIssuePatternErrorKind::Regex(err) => Some(err),
+            ParseIssuePatternErrorKind::MissingIdentifierCapture => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseIssuePatternErrorKind {
+    #[non_exhaustive]
+    MissingIssueKind,
+    #[non_exhaustive]
+    UnknownIssueKind,
+    #[non_exhaustive]
+    Regex(regex::Error),
+    #[non_exhaustive]
+    MissingIdentifierCapture,
+}
+
+impl IssuePattern {
+    fn new(kind: IssueKind, scope: PatternScope, regex: Regex) -> Self {
+        Self { kind, scope, regex }
+    }
+
+    fn reference(&self, captures: &regex::Captures) -> Option<IssueReference> {
+        let intent = match captures.name(CLOSING_CAPTURE) {
+            Some(_) => IssueIntent::Closes,
+            None => IssueIntent::References,
+        };
+        Some(IssueReference {
+            issue: self.kind.issue(captures)?,
+            intent,
+        })
+    }
+
+    fn captures(&self, commit_message: &str) -> Option<IssueReference> {
+        self.regex
+            .captures(commit_message)
+            .and_then(|captures| self.reference(&captures))
+    }
+
+    fn captures_iter<'a>(
+        &'a self,
+        commit_message: &'a str,
+    ) -> impl Iterator<Item = IssueReference> + 'a {
+        self.regex
+            .captures_iter(commit_message)
+            .filter_map(|captures| self.reference(&captures))
+    }
+}
+
+/// Parse an issue pattern of the form `<kind>:[<scope>:]<regex>`, for example
+/// `ticket:^Jira:\s+(?P<id>\S+)` or `github:message:^Refs\s+#(?P<id>\d+)`.
+///
+/// With the `trailers` scope, the default, the regex is matched against the
+/// commit's trailers, one normalized `Key: value` line per trailer. With the
+/// `message` scope, it is matched against the whole commit message. Either
+/// way it is compiled in multi-line mode, so `^` and `$` match at line
+/// boundaries.
+impl FromStr for IssuePattern {
+    type Err = ParseIssuePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |kind| ParseIssuePatternError {
+            pattern: s.to_owned(),
+            kind,
+        };
+        let (kind, regex) = s
+            .split_once(':')
+            .ok_or_else(|| error(ParseIssuePatternErrorKind::MissingIssueKind))?;
+        let kind: IssueKind = kind.parse()?;
+        let (scope, regex) = match regex.split_once(':') {
+            Some(("message", regex)) => (PatternScope::Message, regex),
+            Some(("trailers", regex)) => (PatternScope::Trailers, regex),
+            _ => (PatternScope::Trailers, regex),
+        };
+        let regex = RegexBuilder::new(regex)
+            .multi_line(true)
+            .build()
+            .map_err(|err| error(ParseIssuePatternErrorKind::Regex(err)))?;
+        if !regex
+            .capture_names()
+            .any(|name| name == Some(ISSUE_IDENTIFIER_CAPTURE))
+        {
+            return Err(error(ParseIssuePatternErrorKind::MissingIdentifierCapture));
         }
+        Ok(Self::new(kind, scope, regex))
+    }
+}
 
-        let regex_github_issue = regex!(r"(?im)^(?:closes|close|closed|fixes|fixed)\s+#(\d+)");
-        if let Some(github_captures) = regex_github_issue.captures(commit_message.as_ref()) {
-            return Some(Issue::GitHub(
-                github_captures[github_captures.len() - 1].to_owned(),
-            ));
+/// The ordered set of patterns used to recognize issue references.
+///
+/// User-configured patterns are tried first, in the order they were
+/// configured, followed by the built-in `Ticket:`/`Jira:`/`Linear:` trailer,
+/// Jira and Linear URL, and GitHub closing-keyword patterns.
+///
+/// Trailer patterns only see what git itself considers a trailer. URL and
+/// GitHub closing-keyword patterns see the whole message, since that is
+/// where GitHub looks for them.
+#[derive(Clone, Debug)]
+pub struct IssuePatterns {
+    patterns: Vec<IssuePattern>,
+    trailer_separators: String,
+}
+
+impl Default for IssuePatterns {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                IssuePattern::new(
+                    IssueKind::WorkTracker,
+                    PatternScope::Trailers,
+                    regex!(
+                        r"(?m)^(?:(?P<closes>Closes) )?(?:Ticket|Jira|Linear): (?P<id>[^\s/]+)(?:\s|$)"
+                    )
+                        .clone(),
+                ),
+                // Jira issue URLs, e.g. https://acme.atlassian.net/browse/ABC-123
+                IssuePattern::new(
+                    IssueKind::WorkTracker,
+                    PatternScope::Message,
+                    regex!(
+                        r"(?im)^(?:(?:(?P<closes>closes|close|closed|fixes|fixed)|[\w-]+):?\s+)?https?://\S+/browse/(?P<id>[A-Z][A-Z0-9_]*-\d+)"
+                    )
+                    .clone(),
+                ),
+                // Linear issue URLs, e.g. https://linear.app/acme/issue/ENG-42/title-slug
+                IssuePattern::new(
+                    IssueKind::WorkTracker,
+                    PatternScope::Message,
+                    regex!(
+                        r"(?im)^(?:(?:(?P<closes>closes|close|closed|fixes|fixed)|[\w-]+):?\s+)?https?://linear\.app/[\w.-]+/issue/(?P<id>[A-Z][A-Z0-9]*-\d+)"
+                    )
+                    .clone(),
+                ),
+                IssuePattern::new(
+                    IssueKind::GitHub,
+                    PatternScope::Message,
+                    regex!(r"(?im)^(?P<closes>closes|close|closed|fixes|fixed)\s+#(?P<id>\d+)")
+                        .clone(),
+                ),
+                IssuePattern::new(
+                    IssueKind::GitHub,
+                    PatternScope::Message,
+                    regex!(
+                        r"(?im)^(?P<closes>closes|close|closed|fixes|fixed)\s+(?P<owner>[\w.-]+)/(?P<repo>[\w.-]+)#(?P<id>\d+)"
+                    )
+                    .clone(),
+                ),
+                IssuePattern::new(
+                    IssueKind::GitHub,
+                    PatternScope::Message,
+                    regex!(
+                        r"(?im)^(?:(?P<closes>closes|close|closed|fixes|fixed)\s+)?https?://github\.com/(?P<owner>[\w.-]+)/(?P<repo>[\w.-]+)/issues/(?P<id>\d+)"
+                    )
+                    .clone(),
+                ),
+            ],
+            trailer_separators: trailer::DEFAULT_SEPARATORS.to_owned(),
         }
-        None
+    }
+}
+
+impl FromIterator<IssuePattern> for IssuePatterns {
+    /// Collect user-configured patterns, followed by the built-in patterns.
+    fn from_iter<T: IntoIterator<Item = IssuePattern>>(iter: T) -> Self {
+        let defaults = Self::default();
+        let mut patterns: Vec<IssuePattern> = iter.into_iter().collect();
+        patterns.extend(defaults.patterns);
+        Self {
+            patterns,
+            ..defaults
+        }
+    }
+}
+
+impl IssuePatterns {
+    /// Use `separators` to separate trailer keys from values, as configured
+    /// by git's `trailer.separators`.
+    pub fn with_trailer_separators(self, separators: String) -> Self {
+        Self {
+            trailer_separators: separators,
+            ..self
+        }
+    }
+
+    /// Parse the trailers of a commit message.
+    pub fn trailers<S: AsRef<str>>(&self, commit_message: S) -> Vec<trailer::Trailer> {
+        trailer::parse(commit_message.as_ref(), &self.trailer_separators)
+    }
+
+    /// Every pattern, paired with the text it should be matched against.
+    fn haystacks<'a>(
+        &'a self,
+        commit_message: &'a str,
+        trailers: &'a str,
+    ) -> impl Iterator<Item = (&'a IssuePattern, &'a str)> + 'a {
+        self.patterns.iter().map(move |pattern| {
+            let haystack = match pattern.scope {
+                PatternScope::Trailers => trailers,
+                PatternScope::Message => commit_message,
+            };
+            (pattern, haystack)
+        })
+    }
+
+    fn rendered_trailers(&self, commit_message: &str) -> String {
+        self.trailers(commit_message)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn parse_from_commit_message<S: AsRef<str>>(&self, commit_message: S) -> Option<Issue> {
+        let trailers = self.rendered_trailers(commit_message.as_ref());
+        let reference = self
+            .haystacks(commit_message.as_ref(), &trailers)
+            .find_map(|(pattern, haystack)| pattern.captures(haystack));
+        reference.map(|reference| reference.issue)
+    }
+
+    /// Parse every distinct issue referenced in a commit message.
+    ///
+    /// The primary issue, the one returned by `parse_from_commit_message`,
+    /// comes first.
+    pub fn parse_all_from_commit_message<S: AsRef<str>>(&self, commit_message: S) -> Vec<Issue> {
+        self.parse_references_from_commit_message(commit_message)
+            .into_iter()
+            .map(|reference| reference.issue)
+            .collect()
+    }
+
+    /// Parse every distinct issue referenced in a commit message, along with
+    /// whether the commit closes it.
+    ///
+    /// An issue that is both closed and referenced is closed.
+    pub fn parse_references_from_commit_message<S: AsRef<str>>(
+        &self,
+        commit_message: S,
+    ) -> Vec<IssueReference> {
+        let trailers = self.rendered_trailers(commit_message.as_ref());
+        let mut references: Vec<IssueReference> = Vec::new();
+        for reference in self
+            .haystacks(commit_message.as_ref(), &trailers)
+            .flat_map(|(pattern, haystack)| pattern.captures_iter(haystack))
+        {
+            merge_reference(&mut references, reference);
+        }
+        references
+    }
+}
+
+impl Issue {
+    pub fn parse_from_commit_message<S: AsRef<str>>(commit_message: S) -> Option<Issue> {
+        IssuePatterns::default().parse_from_commit_message(commit_message)
+    }
+
+    pub fn parse_all_from_commit_message<S: AsRef<str>>(commit_message: S) -> Vec<Issue> {
+        IssuePatterns::default().parse_all_from_commit_message(commit_message)
+    }
+
+    pub fn parse_references_from_commit_message<S: AsRef<str>>(
+        commit_message: S,
+    ) -> Vec<IssueReference> {
+        IssuePatterns::default().parse_references_from_commit_message(commit_message)
     }
 
     pub fn issue_identifier(&self) -> &str {
         match self {
             Issue::WorkTracker(id) => id,
             Issue::GitHub(issue) => issue,
+            Issue::GitHubCrossRepository { number, .. } => number,
+        }
+    }
+
+    /// How this issue is written when referenced from a PR body, e.g. `AB-123`,
+    /// `#123` or `org/other-repo#123`.
+    pub fn reference(&self) -> String {
+        match self {
+            Issue::WorkTracker(id) => id.clone(),
+            Issue::GitHub(issue) => format!("#{issue}"),
+            Issue::GitHubCrossRepository {
+                owner,
+                repository,
+                number,
+            } => format!("{owner}/{repository}#{number}"),
         }
     }
 }
 
 impl Display for Issue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}{}",
-            match self {
-                Issue::WorkTracker(_) => "Issue ",
-                Issue::GitHub(_) => "GitHub #",
-            },
-            self.issue_identifier()
-        )
+        match self {
+            Issue::WorkTracker(_) => write!(f, "Issue {}", self.issue_identifier()),
+            Issue::GitHub(_) | Issue::GitHubCrossRepository { .. } => {
+                write!(f, "GitHub {}", self.reference())
+            }
+        }
     }
 }
 
@@ -72,6 +512,16 @@ mod test {
         assert_eq!(format!("{issue}"), "GitHub #123");
     }
 
+    #[test]
+    fn display_github_cross_repository_issue() {
+        let issue = Issue::GitHubCrossRepository {
+            owner: "org".to_string(),
+            repository: "other-repo".to_string(),
+            number: "123".to_string(),
+        };
+        assert_eq!(format!("{issue}"), "GitHub org/other-repo#123");
+    }
+
     macro_rules! test_parses {
         ($unit_test:ident, $input:expr, $output:expr) => {
             #[test]
@@ -215,6 +665,134 @@ Closes #123
         Issue::GitHub("123".to_string())
     );
 
+    test_parses!(
+        successfully_parse_jira_trailer,
+        r#"
+feat(foo): add hyperdrive
+
+Jira: ABC-123
+"#,
+        Issue::WorkTracker("ABC-123".to_string())
+    );
+
+    test_parses!(
+        successfully_parse_linear_trailer,
+        r#"
+feat(foo): add hyperdrive
+
+Closes Linear: ENG-42
+"#,
+        Issue::WorkTracker("ENG-42".to_string())
+    );
+
+    test_parses!(
+        successfully_parse_jira_url,
+        r#"
+feat(foo): add hyperdrive
+
+https://acme.atlassian.net/browse/ABC-123
+"#,
+        Issue::WorkTracker("ABC-123".to_string())
+    );
+
+    test_parses!(
+        successfully_parse_jira_url_in_trailer,
+        r#"
+feat(foo): add hyperdrive
+
+Jira: https://jira.acme.com/browse/ABC-123
+"#,
+        Issue::WorkTracker("ABC-123".to_string())
+    );
+
+    test_parses!(
+        successfully_parse_linear_url,
+        r#"
+feat(foo): add hyperdrive
+
+Closes https://linear.app/acme/issue/ENG-42/add-hyperdrive
+"#,
+        Issue::WorkTracker("ENG-42".to_string())
+    );
+
+    test_parses!(
+        successfully_parse_github_cross_repository_issue,
+        r#"
+fix(foo): repair hyperdrive
+
+Closes org/other-repo#123
+"#,
+        Issue::GitHubCrossRepository {
+            owner: "org".to_string(),
+            repository: "other-repo".to_string(),
+            number: "123".to_string(),
+        }
+    );
+
+    test_parses!(
+        successfully_parse_github_issue_url,
+        r#"
+fix(foo): repair hyperdrive
+
+https://github.com/org/other.repo/issues/123
+"#,
+        Issue::GitHubCrossRepository {
+            owner: "org".to_string(),
+            repository: "other.repo".to_string(),
+            number: "123".to_string(),
+        }
+    );
+
+    test_parses!(
+        successfully_parse_github_issue_url_with_closing_keyword,
+        r#"
+fix(foo): repair hyperdrive
+
+Fixes https://github.com/org/other-repo/issues/123
+"#,
+        Issue::GitHubCrossRepository {
+            owner: "org".to_string(),
+            repository: "other-repo".to_string(),
+            number: "123".to_string(),
+        }
+    );
+
+    #[test]
+    fn cross_repository_reference_is_fully_qualified() {
+        let message = "fix: x\n\nFixes https://github.com/org/other-repo/issues/9";
+        let issue = Issue::parse_from_commit_message(message).unwrap();
+        assert_eq!(issue.reference(), "org/other-repo#9");
+    }
+
+    test_parses!(
+        successfully_parse_lowercase_trailer_key_and_issue_key,
+        r#"
+feat(foo): add hyperdrive
+
+ticket: ab-123
+"#,
+        Issue::WorkTracker("AB-123".to_string())
+    );
+
+    #[test]
+    fn ticket_outside_trailer_block_is_ignored() {
+        let message = r#"
+feat(foo): add hyperdrive
+
+Quoting the old commit:
+
+    Ticket: AB-123
+
+and nothing else.
+"#;
+        assert_eq!(Issue::parse_from_commit_message(message), None);
+    }
+
+    #[test]
+    fn ticket_in_subject_is_ignored() {
+        assert_eq!(Issue::parse_from_commit_message("Ticket: AB-123"), None);
+    }
+
     #[test]
     fn unsuccessfully_parse_from_commit_message() {
         let message = "feat(foo): add hyperdrive";
@@ -225,6 +803,201 @@ Closes #123
         );
     }
 
+    mod issue_patterns {
+        use crate::issue::{Issue, IssuePattern, IssuePatterns};
+
+        fn patterns(configured: &[&str]) -> IssuePatterns {
+            configured
+                .iter()
+                .map(|pattern| pattern.parse::<IssuePattern>().unwrap())
+                .collect()
+        }
+
+        #[test]
+        fn configured_pattern_recognizes_custom_trailer() {
+            let patterns = patterns(&[r"ticket:^Jira:\s+(?P<id>\S+)"]);
+            let issue = patterns.parse_from_commit_message("feat: x\n\nJira: AB-7\n");
+            assert_eq!(issue, Some(Issue::WorkTracker("AB-7".to_string())));
+        }
+
+        #[test]
+        fn configured_pattern_maps_to_github() {
+            let patterns = patterns(&[r"github:^Refs:?\s+#(?P<id>\d+)"]);
+            let issue = patterns.parse_from_commit_message("fix: x\n\nrefs: #42\n");
+            assert_eq!(issue, Some(Issue::GitHub("42".to_string())));
+        }
+
+        #[test]
+        fn configured_pattern_matches_normalized_trailers_only() {
+            let patterns = patterns(&[r"ticket:^Story-Id: (?P<id>\S+)"]);
+            let issue = patterns.parse_from_commit_message("feat: x\n\nstory-ID: ST-2\n");
+            assert_eq!(issue, Some(Issue::WorkTracker("ST-2".to_string())));
+            // Only the trailer block holds trailers
+            let issue = patterns
+                .parse_from_commit_message("feat: x\n\nStory-Id: ST-2\nsee above\n\nDone.\n");
+            assert_eq!(issue, None);
+        }
+
+        #[test]
+        fn configured_message_pattern_matches_the_whole_message() {
+            let patterns = patterns(&[r"github:message:^Refs\s+#(?P<id>\d+)"]);
+            let issue = patterns.parse_from_commit_message("fix: x\n\nRefs #42\n\nDone.\n");
+            assert_eq!(issue, Some(Issue::GitHub("42".to_string())));
+        }
+
+        #[test]
+        fn configured_patterns_take_precedence_over_built_in_patterns() {
+            let patterns = patterns(&[r"ticket:^Story:\s+(?P<id>\S+)"]);
+            let issue =
+                patterns.parse_from_commit_message("feat: x\n\nTicket: AB-1\nStory: ST-2\n");
+            assert_eq!(issue, Some(Issue::WorkTracker("ST-2".to_string())));
+        }
+
+        #[test]
+        fn built_in_patterns_remain_when_patterns_are_configured() {
+            let patterns = patterns(&[r"ticket:^Story:\s+(?P<id>\S+)"]);
+            let issue = patterns.parse_from_commit_message("fix: x\n\nCloses #9\n");
+            assert_eq!(issue, Some(Issue::GitHub("9".to_string())));
+        }
+
+        #[test]
+        fn pattern_without_kind_is_rejected() {
+            assert!(r"^Jira\s+(?P<id>\S+)".parse::<IssuePattern>().is_err());
+        }
+
+        #[test]
+        fn pattern_with_unknown_kind_is_rejected() {
+            assert!(r"gitlab:^Jira:\s+(?P<id>\S+)"
+                .parse::<IssuePattern>()
+                .is_err());
+        }
+
+        #[test]
+        fn pattern_without_identifier_capture_is_rejected() {
+            assert!(r"ticket:^Jira:\s+(\S+)".parse::<IssuePattern>().is_err());
+        }
+
+        #[test]
+        fn pattern_with_invalid_regex_is_rejected() {
+            assert!(r"ticket:^Jira:\s+(?P<id>\S+"
+                .parse::<IssuePattern>()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn parse_all_returns_every_reference_primary_first() {
+        let message = "fix: x\n\nCloses #42\nFixes #43\n\nTicket: AB-1\n";
+        assert_eq!(
+            Issue::parse_all_from_commit_message(message),
+            vec![
+                Issue::WorkTracker("AB-1".to_string()),
+                Issue::GitHub("42".to_string()),
+                Issue::GitHub("43".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_all_deduplicates_references() {
+        let message = "fix: x\n\nCloses #42\nFixes #42\n";
+        assert_eq!(
+            Issue::parse_all_from_commit_message(message),
+            vec![Issue::GitHub("42".to_string())]
+        );
+    }
+
+    #[test]
+    fn parse_all_without_references_is_empty() {
+        assert!(Issue::parse_all_from_commit_message("feat(foo): add hyperdrive").is_empty());
+    }
+
+    mod intent {
+        use super::super::{Issue, IssueIntent, IssueReference};
+
+        fn intents(message: &str) -> Vec<(String, IssueIntent)> {
+            Issue::parse_references_from_commit_message(message)
+                .into_iter()
+                .map(|reference| (reference.issue.reference(), reference.intent))
+                .collect()
+        }
+
+        #[test]
+        fn ticket_trailer_references() {
+            assert_eq!(
+                intents("feat: x\n\nTicket: AB-1\n"),
+                vec![("AB-1".to_string(), IssueIntent::References)]
+            );
+        }
+
+        #[test]
+        fn closes_ticket_trailer_closes() {
+            assert_eq!(
+                intents("feat: x\n\nCloses Ticket: AB-1\n"),
+                vec![("AB-1".to_string(), IssueIntent::Closes)]
+            );
+        }
+
+        #[test]
+        fn github_keyword_closes() {
+            assert_eq!(
+                intents("fix: x\n\nFixes org/repo#3\nCloses #4\n"),
+                vec![
+                    ("#4".to_string(), IssueIntent::Closes),
+                    ("org/repo#3".to_string(), IssueIntent::Closes),
+                ]
+            );
+        }
+
+        #[test]
+        fn bare_urls_reference() {
+            assert_eq!(
+                intents(
+                    "fix: x\n\nhttps://github.com/org/repo/issues/3\n\
+                     Fixes https://acme.atlassian.net/browse/AB-2\n"
+                ),
+                vec![
+                    ("AB-2".to_string(), IssueIntent::Closes),
+                    ("org/repo#3".to_string(), IssueIntent::References),
+                ]
+            );
+        }
+
+        #[test]
+        fn closing_wins_over_referencing() {
+            assert_eq!(
+                intents(
+                    "feat: x\n\nhttps://acme.atlassian.net/browse/AB-1\n\nCloses Ticket: AB-1\n"
+                ),
+                vec![("AB-1".to_string(), IssueIntent::Closes)]
+            );
+        }
+
+        #[test]
+        fn display_footer_line() {
+            let closes = IssueReference {
+                issue: Issue::GitHub("12".to_string()),
+                intent: IssueIntent::Closes,
+            };
+            let refs = IssueReference {
+                issue: Issue::WorkTracker("ABC-3".to_string()),
+                intent: IssueIntent::References,
+            };
+            assert_eq!(closes.to_string(), "Closes #12");
+            assert_eq!(refs.to_string(), "Refs ABC-3");
+        }
+    }
+
+    #[test]
+    fn reference_github_issue() {
+        assert_eq!(Issue::GitHub("42".to_string()).reference(), "#42");
+    }
+
+    #[test]
+    fn reference_work_tracker_issue() {
+        assert_eq!(Issue::WorkTracker("AB-1".to_string()).reference(), "AB-1");
+    }
+
     use proptest::prelude::*;
 
     proptest! {
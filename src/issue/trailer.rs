@@ -0,0 +1,284 @@
+//! Commit message trailers, parsed the way `git interpret-trailers` parses them.
+//!
+//! Only the last paragraph of a message can hold trailers, and only when git
+//! would consider it a trailer block: every line is a trailer (or a
+//! continuation of one), or at least a quarter of the lines are trailers and
+//! one of them is a git-generated trailer like `Signed-off-by:`.
+//!
+//! Beyond git's rules, a trailer key may be preceded by `Closes `, so the
+//! long-documented `Closes Ticket: AB-123` form keeps working.
+
+use std::fmt::Display;
+
+/// The trailer separators git uses when `trailer.separators` is unset.
+pub const DEFAULT_SEPARATORS: &str = ":";
+
+/// Prefixes of trailers git itself generates.
+const GIT_GENERATED_PREFIXES: &[&str] = &["Signed-off-by: ", "(cherry picked from commit "];
+
+/// Key prefix accepted in addition to git's trailer token rules.
+const CLOSING_PREFIX: &str = "closes ";
+
+/// A single `Key: value` trailer.
+///
+/// Keys are normalized to title case (`ticket` and `TICKET` both become
+/// `Ticket`), and continuation lines are unfolded into the value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Display for Trailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.value)
+    }
+}
+
+/// Title-case each word of a trailer key, e.g. `disjoint-GROUP` becomes
+/// `Disjoint-Group`.
+fn normalize_key(key: &str) -> String {
+    let mut normalized = String::with_capacity(key.len());
+    let mut start_of_word = true;
+    for c in key.chars() {
+        match start_of_word {
+            true => normalized.extend(c.to_uppercase()),
+            false => normalized.extend(c.to_lowercase()),
+        }
+        start_of_word = c == '-' || c == ' ';
+    }
+    normalized
+}
+
+/// Return the byte offset of the separator in a trailer line, following
+/// git's `find_separator`: the token is made of alphanumerics and hyphens,
+/// optionally followed by whitespace.
+fn find_separator(line: &str, separators: &str) -> Option<usize> {
+    let offset = match line.get(..CLOSING_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(CLOSING_PREFIX) => CLOSING_PREFIX.len(),
+        _ => 0,
+    };
+    let mut whitespace_found = false;
+    for (i, c) in line[offset..].char_indices() {
+        if separators.contains(c) {
+            return match offset + i {
+                0 => None,
+                separator => Some(separator),
+            };
+        }
+        if !whitespace_found && (c.is_alphanumeric() || c == '-') {
+            continue;
+        }
+        if i != 0 && (c == ' ' || c == '\t') {
+            whitespace_found = true;
+            continue;
+        }
+        break;
+    }
+    None
+}
+
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#')
+}
+
+/// Return the lines of the trailer block of `message`, if it has one.
+fn trailer_block<'a>(message: &'a str, separators: &str) -> Option<Vec<&'a str>> {
+    let lines: Vec<&str> = message.lines().collect();
+    // Ignore trailing blank lines.
+    let end = lines.iter().rposition(|line| !line.trim().is_empty())? + 1;
+    // The last paragraph starts after the last blank line.
+    let start = lines[..end]
+        .iter()
+        .rposition(|line| line.trim().is_empty())
+        .map(|blank| blank + 1)?;
+    // The subject paragraph can never hold trailers.
+    if !lines[..start].iter().any(|line| !line.trim().is_empty()) {
+        return None;
+    }
+    let paragraph = &lines[start..end];
+
+    let mut trailer_lines = 0;
+    let mut non_trailer_lines = 0;
+    let mut possible_continuation_lines = 0;
+    let mut recognized_prefix = false;
+    for line in paragraph.iter().rev() {
+        if is_comment(line) {
+            continue;
+        }
+        if is_continuation(line) {
+            possible_continuation_lines += 1;
+            continue;
+        }
+        match find_separator(line, separators) {
+            Some(_) => {
+                trailer_lines += 1;
+                possible_continuation_lines = 0;
+                recognized_prefix |= GIT_GENERATED_PREFIXES
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix));
+            }
+            None => {
+                non_trailer_lines += possible_continuation_lines + 1;
+                possible_continuation_lines = 0;
+            }
+        }
+    }
+    non_trailer_lines += possible_continuation_lines;
+
+    let is_trailer_block = trailer_lines > 0
+        && (non_trailer_lines == 0
+            || (recognized_prefix && trailer_lines * 3 >= non_trailer_lines));
+    is_trailer_block.then(|| paragraph.to_vec())
+}
+
+/// Parse the trailers of a commit message.
+///
+/// `separators` lists the characters that separate a trailer's key from its
+/// value, as configured by git's `trailer.separators`.
+pub fn parse(message: &str, separators: &str) -> Vec<Trailer> {
+    let mut trailers: Vec<Trailer> = Vec::new();
+    let mut in_trailer = false;
+    for line in trailer_block(message, separators).unwrap_or_default() {
+        if is_comment(line) {
+            continue;
+        }
+        if is_continuation(line) {
+            if let (true, Some(trailer)) = (in_trailer, trailers.last_mut()) {
+                let continuation = line.trim();
+                if !continuation.is_empty() {
+                    if !trailer.value.is_empty() {
+                        trailer.value.push(' ');
+                    }
+                    trailer.value.push_str(continuation);
+                }
+            }
+            continue;
+        }
+        in_trailer = match find_separator(line, separators) {
+            Some(separator) => {
+                let value_start =
+                    separator + line[separator..].chars().next().map_or(0, char::len_utf8);
+                trailers.push(Trailer {
+                    key: normalize_key(line[..separator].trim()),
+                    value: line[value_start..].trim().to_owned(),
+                });
+                true
+            }
+            None => false,
+        };
+    }
+    trailers
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, Trailer, DEFAULT_SEPARATORS};
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn check(message: &str, expected: &[Trailer]) {
+        assert_eq!(parse(message, DEFAULT_SEPARATORS), expected);
+    }
+
+    #[test]
+    fn parses_last_paragraph() {
+        check(
+            "feat: x\n\nbody\n\nTicket: AB-1\nReviewed-by: Someone\n",
+            &[trailer("Ticket", "AB-1"), trailer("Reviewed-By", "Someone")],
+        );
+    }
+
+    #[test]
+    fn subject_is_never_a_trailer() {
+        check("Ticket: AB-1", &[]);
+    }
+
+    #[test]
+    fn earlier_paragraphs_are_ignored() {
+        check("feat: x\n\nTicket: AB-1\n\nSome closing prose.\n", &[]);
+    }
+
+    #[test]
+    fn quoted_trailer_in_body_is_ignored() {
+        check("feat: x\n\n```\nTicket: AB-1\n```\n\nMore prose.\n", &[]);
+    }
+
+    #[test]
+    fn mixed_paragraph_is_not_a_trailer_block() {
+        check("feat: x\n\nThis paragraph mentions\nTicket: AB-1\n", &[]);
+    }
+
+    #[test]
+    fn git_generated_trailer_allows_some_prose() {
+        check(
+            "feat: x\n\nprose line\nTicket: AB-1\nSigned-off-by: A <a@b.c>\n",
+            &[
+                trailer("Ticket", "AB-1"),
+                trailer("Signed-Off-By", "A <a@b.c>"),
+            ],
+        );
+    }
+
+    #[test]
+    fn continuation_lines_are_unfolded() {
+        check(
+            "feat: x\n\nNote: first line\n  second line\nTicket: AB-1\n",
+            &[
+                trailer("Note", "first line second line"),
+                trailer("Ticket", "AB-1"),
+            ],
+        );
+    }
+
+    #[test]
+    fn keys_are_title_cased() {
+        check(
+            "feat: x\n\nticket: AB-1\nDISJOINT-GROUP: docs\n",
+            &[trailer("Ticket", "AB-1"), trailer("Disjoint-Group", "docs")],
+        );
+    }
+
+    #[test]
+    fn whitespace_before_separator_is_allowed() {
+        check("feat: x\n\nTicket : AB-1\n", &[trailer("Ticket", "AB-1")]);
+    }
+
+    #[test]
+    fn closes_prefix_is_kept_in_key() {
+        check(
+            "feat: x\n\nCloses Ticket: AB-1\n",
+            &[trailer("Closes Ticket", "AB-1")],
+        );
+    }
+
+    #[test]
+    fn configured_separators_are_honoured() {
+        assert_eq!(
+            parse("fix: x\n\nCloses #12\nTicket= AB-1\n", "#="),
+            &[trailer("Closes", "12"), trailer("Ticket", "AB-1")],
+        );
+    }
+
+    #[test]
+    fn default_separator_does_not_match_other_characters() {
+        check("fix: x\n\nTicket= AB-1\n", &[]);
+    }
+
+    #[test]
+    fn trailing_blank_lines_are_ignored() {
+        check(
+            "feat: x\n\nTicket: AB-1\n\n\n",
+            &[trailer("Ticket", "AB-1")],
+        );
+    }
+}
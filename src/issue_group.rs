@@ -2,7 +2,7 @@ use std::{error::Error, fmt::Display};
 
 use git2::Commit;
 
-use crate::issue::Issue;
+use crate::issue::{self, trailer::Trailer, Issue, IssueIntent, IssuePatterns, IssueReference};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GitCommitSummary(pub String);
@@ -60,10 +60,86 @@ impl TryFrom<&Commit<'_>> for GitCommitSummary {
     }
 }
 
+/// Trailer key naming a commit's group explicitly.
+const GROUP_NAME_TRAILER: &str = "Disjoint-Group";
+
+/// A group of commits named explicitly with a `Disjoint-Group:` trailer.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupName(pub String);
+
+impl GroupName {
+    pub fn from_trailers(trailers: &[Trailer]) -> Option<Self> {
+        trailers
+            .iter()
+            .find(|trailer| trailer.key == GROUP_NAME_TRAILER && !trailer.value.is_empty())
+            .map(|trailer| Self(trailer.value.clone()))
+    }
+}
+
+impl Display for GroupName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum IssueGroup {
     Issue(Issue),
     Commit(GitCommitSummary),
+    Named(GroupName),
+    /// Several groups combined into one PR with `--combine`, in the order
+    /// they were named.
+    Combined(Vec<IssueGroup>),
+}
+
+impl IssueGroup {
+    /// Whether `value` names this group, e.g. `AB-100`, `#12`, or the name
+    /// given with a `Disjoint-Group:` trailer.
+    pub fn is_named_by(&self, value: &str) -> bool {
+        match self {
+            IssueGroup::Issue(Issue::WorkTracker(key)) => key.eq_ignore_ascii_case(value),
+            IssueGroup::Issue(issue) => issue.reference() == value,
+            IssueGroup::Commit(summary) => summary.0 == value,
+            IssueGroup::Named(name) => name.0 == value,
+            IssueGroup::Combined(members) => members.iter().any(|member| member.is_named_by(value)),
+        }
+    }
+
+    /// The issues this group is for: its own issue, or the issues of the
+    /// groups it combines.
+    pub fn issues(&self) -> Vec<&Issue> {
+        match self {
+            IssueGroup::Issue(issue) => vec![issue],
+            IssueGroup::Commit(_) | IssueGroup::Named(_) => Vec::new(),
+            IssueGroup::Combined(members) => members.iter().flat_map(IssueGroup::issues).collect(),
+        }
+    }
+
+    /// Every issue referenced by `commits`, this group's own issues first and
+    /// the rest in order of first appearance.
+    ///
+    /// An issue is closed if any of the commits closes it.
+    pub fn references<'a, 'repo: 'a>(
+        &self,
+        issue_patterns: &IssuePatterns,
+        commits: impl IntoIterator<Item = &'a Commit<'repo>>,
+    ) -> Vec<IssueReference> {
+        let mut references: Vec<IssueReference> = self
+            .issues()
+            .into_iter()
+            .map(|own| IssueReference {
+                issue: own.clone(),
+                intent: IssueIntent::References,
+            })
+            .collect();
+        for reference in commits.into_iter().flat_map(|commit| {
+            issue_patterns
+                .parse_references_from_commit_message(commit.message().unwrap_or_default())
+        }) {
+            issue::merge_reference(&mut references, reference);
+        }
+        references
+    }
 }
 
 impl Display for IssueGroup {
@@ -71,6 +147,16 @@ impl Display for IssueGroup {
         match self {
             IssueGroup::Issue(issue) => write!(f, "{issue}"),
             IssueGroup::Commit(commit) => write!(f, "{commit}"),
+            IssueGroup::Named(name) => write!(f, "Group {name}"),
+            IssueGroup::Combined(members) => {
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " + ")?;
+                    }
+                    write!(f, "{member}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -81,6 +167,12 @@ impl From<GitCommitSummary> for IssueGroup {
     }
 }
 
+impl From<GroupName> for IssueGroup {
+    fn from(value: GroupName) -> Self {
+        Self::Named(value)
+    }
+}
+
 impl From<Issue> for IssueGroup {
     fn from(value: Issue) -> Self {
         Self::Issue(value)
@@ -89,7 +181,10 @@ impl From<Issue> for IssueGroup {
 
 #[cfg(test)]
 mod test_display {
-    use crate::{issue::Issue, issue_group::GitCommitSummary};
+    use crate::{
+        issue::Issue,
+        issue_group::{GitCommitSummary, GroupName},
+    };
 
     use super::IssueGroup;
 
@@ -109,4 +204,59 @@ mod test_display {
             "this is a cool summary",
         );
     }
+
+    #[test]
+    fn display_human_readable_named_group() {
+        check(
+            GroupName(String::from("docs cleanup")),
+            "Group docs cleanup",
+        );
+    }
+
+    #[test]
+    fn display_human_readable_combined_group() {
+        check(
+            IssueGroup::Combined(vec![
+                Issue::WorkTracker("AB-1".to_string()).into(),
+                GroupName(String::from("docs")).into(),
+            ]),
+            "Issue AB-1 + Group docs",
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_group_name {
+    use super::GroupName;
+    use crate::issue::trailer::{self, DEFAULT_SEPARATORS};
+
+    fn parse(message: &str) -> Option<GroupName> {
+        GroupName::from_trailers(&trailer::parse(message, DEFAULT_SEPARATORS))
+    }
+
+    #[test]
+    fn parse_group_name_trailer() {
+        let message = "docs: tidy\n\nDisjoint-Group: docs cleanup  \nTicket: AB-1\n";
+        assert_eq!(
+            parse(message),
+            Some(GroupName(String::from("docs cleanup")))
+        );
+    }
+
+    #[test]
+    fn parse_group_name_trailer_is_case_insensitive() {
+        let message = "docs: tidy\n\ndisjoint-group: docs\n";
+        assert_eq!(parse(message), Some(GroupName(String::from("docs"))));
+    }
+
+    #[test]
+    fn parse_without_group_name_trailer() {
+        assert_eq!(parse("docs: tidy"), None);
+    }
+
+    #[test]
+    fn group_name_outside_trailers_is_ignored() {
+        let message = "docs: tidy\n\nDisjoint-Group: docs\n\nTrailing prose.\n";
+        assert_eq!(parse(message), None);
+    }
 }
@@ -5,19 +5,42 @@ use std::{
     io::{self, Write},
 };
 
-use git2::Commit;
+use git2::{Commit, Oid};
 use indexmap::IndexMap;
 
 use crate::{
+    autosquash,
     cli::{
-        CommitGrouping, CommitsToConsider, OverlayCommitsIntoOnePullRequest,
-        PromptUserToChooseCommits,
+        CommitGrouping, CommitsToConsider, GroupBy, MultipleIssuePolicy,
+        OverlayCommitsIntoOnePullRequest, PromptUserToChooseCommits,
+    },
+    config::{Config, GroupByPrecedence},
+    git2_repository::HistoryOrder,
+    interact::{
+        prompt_user, CommitChoice, CommitWhitelist, SelectIssuesError, SelectIssuesErrorKind,
     },
-    interact::{prompt_user, IssueGroupWhitelist, SelectIssuesError},
     issue::Issue,
-    issue_group::{self, GitCommitSummary, IssueGroup},
+    issue_group::{self, GitCommitSummary, GroupName, IssueGroup},
+    path_group::PathGrouper,
+    scope_group::ConventionalHeader,
 };
 
+/// Issue groups and commits selected on the command line, with `--issue`,
+/// `--exclude-issue` and `--commit`.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    pub issues: Vec<String>,
+    pub excluded_issues: Vec<String>,
+    /// Revisions of commits to select.
+    pub commits: Vec<String>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty() && self.excluded_issues.is_empty() && self.commits.is_empty()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct IssueGroupMap<'repo>(IndexMap<IssueGroup, Vec<Commit<'repo>>>);
 
@@ -48,6 +71,31 @@ impl Display for FromCommitsError {
         match &self.kind {
             FromCommitsErrorKind::FromCommit(_) => write!(f, "unable to get commit summary"),
             FromCommitsErrorKind::IO(_) => write!(f, "unable to write to stream"),
+            FromCommitsErrorKind::Paths(_) => {
+                write!(f, "unable to find the paths a commit touches")
+            }
+            FromCommitsErrorKind::MultipleIssues {
+                commit,
+                summary,
+                issues,
+            } => {
+                write!(
+                    f,
+                    "commit {commit:.7} ({summary:?}) references multiple issues: "
+                )?;
+                for (i, issue) in issues.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{issue}")?;
+                }
+                write!(
+                    f,
+                    "\n\nhelp: reword the commit to reference one issue, or use \
+                     `--multiple-issues primary` to group it under {}",
+                    issues[0]
+                )
+            }
         }
     }
 }
@@ -57,6 +105,8 @@ impl Error for FromCommitsError {
         match &self.kind {
             FromCommitsErrorKind::FromCommit(err) => Some(err),
             FromCommitsErrorKind::IO(err) => Some(err),
+            FromCommitsErrorKind::Paths(err) => Some(err),
+            FromCommitsErrorKind::MultipleIssues { .. } => None,
         }
     }
 }
@@ -67,6 +117,21 @@ pub enum FromCommitsErrorKind {
     FromCommit(issue_group::FromCommitError),
     #[non_exhaustive]
     IO(io::Error),
+    #[non_exhaustive]
+    Paths(git2::Error),
+    /// A commit references more than one issue and the policy is to refuse
+    #[non_exhaustive]
+    MultipleIssues {
+        commit: git2::Oid,
+        summary: String,
+        issues: Vec<Issue>,
+    },
+}
+
+impl From<FromCommitsErrorKind> for FromCommitsError {
+    fn from(kind: FromCommitsErrorKind) -> Self {
+        Self { kind }
+    }
 }
 
 impl From<issue_group::FromCommitError> for FromCommitsError {
@@ -86,6 +151,27 @@ impl From<io::Error> for FromCommitsError {
 }
 
 impl<'repo> IssueGroupMap<'repo> {
+    pub fn iter(&self) -> indexmap::map::Iter<'_, IssueGroup, Vec<Commit<'repo>>> {
+        self.0.iter()
+    }
+
+    /// Where `issue_group` is in the order the groups were found.
+    pub fn position(&self, issue_group: &IssueGroup) -> Option<usize> {
+        self.0.get_index_of(issue_group)
+    }
+
+    /// Move the commits of `from` into `into`, keeping them in history order.
+    pub fn combine(mut self, into: &IssueGroup, from: &IssueGroup, history: &HistoryOrder) -> Self {
+        let Some(mut moved) = self.0.shift_remove(from) else {
+            return self;
+        };
+        if let Some(commits) = self.0.get_mut(into) {
+            commits.append(&mut moved);
+            commits.sort_by_key(|commit| history.key(commit));
+        }
+        self
+    }
+
     fn with_capacity(n: usize) -> Self {
         Self(IndexMap::with_capacity(n))
     }
@@ -96,30 +182,100 @@ impl<'repo> IssueGroupMap<'repo> {
 
     pub fn try_from_commits<I>(
         commits: I,
+        config: &Config,
         commits_to_consider: CommitsToConsider,
         commit_grouping: CommitGrouping,
+        multiple_issues: MultipleIssuePolicy,
+        group_by: GroupBy,
+        repo: &git2::Repository,
     ) -> Result<Self, FromCommitsError>
     where
         I: IntoIterator<Item = Commit<'repo>>,
     {
+        let path_grouper = match group_by {
+            GroupBy::Path => Some(PathGrouper::new(repo).map_err(FromCommitsErrorKind::Paths)?),
+            GroupBy::Commit | GroupBy::Scope | GroupBy::TypeScope => None,
+        };
+        let issue_patterns = &config.issue_patterns;
+        // The group `--group-by` infers for a commit, if any
+        let inferred_group = |commit: &Commit| -> Result<Option<GroupName>, FromCommitsError> {
+            if let Some(path_grouper) = &path_grouper {
+                let name = path_grouper
+                    .group_name(commit)
+                    .map_err(FromCommitsErrorKind::Paths)?;
+                return Ok(Some(name));
+            }
+            Ok(commit
+                .summary()
+                .and_then(ConventionalHeader::parse)
+                .and_then(|header| header.group_name(group_by)))
+        };
         let mut suffix: u32 = 0;
         let mut seen_issue_groups = HashSet::new();
-        let commits_by_issue: IndexMap<IssueGroup, Vec<Commit>> = commits
+        // Set aside fixup!, squash! and amend! commits. Like `git rebase
+        // --autosquash`, they follow the commit they target, so they join
+        // its issue group below whether or not they have a trailer.
+        let (commits, fixups) = autosquash::partition(commits);
+        let mut commits_by_issue: IndexMap<IssueGroup, Vec<Commit>> = commits
             .into_iter()
             // Parse issue from commit message
             .map(
                 |commit| -> Result<Option<(IssueGroup, Commit)>, FromCommitsError> {
-                    let issue = commit.message().and_then(Issue::parse_from_commit_message);
+                    // If:
+                    // - we're grouping commits by issue, and
+                    // - this commit names its group explicitly,
+                    // then add this commit to the named group, regardless of issue.
+                    if commit_grouping == CommitGrouping::ByIssue {
+                        let trailers =
+                            issue_patterns.trailers(commit.message().unwrap_or_default());
+                        if let Some(name) = GroupName::from_trailers(&trailers) {
+                            return Ok(Some((name.into(), commit)));
+                        }
+                    }
+
+                    // If:
+                    // - we're grouping commits by issue, and
+                    // - the `--group-by` group takes precedence over issues,
+                    // then add this commit to its inferred group, if it has one.
+                    if commit_grouping == CommitGrouping::ByIssue
+                        && config.group_by_precedence == GroupByPrecedence::Group
+                    {
+                        if let Some(name) = inferred_group(&commit)? {
+                            return Ok(Some((name.into(), commit)));
+                        }
+                    }
+
+                    let issues = commit
+                        .message()
+                        .map(|message| issue_patterns.parse_all_from_commit_message(message))
+                        .unwrap_or_default();
                     // If:
                     // - we're grouping commits by issue, and
                     // - this commit includes an issue,
-                    // then add this commit to that issue's group.
+                    // then add this commit to its primary issue's group.
                     if commit_grouping == CommitGrouping::ByIssue {
-                        if let Some(issue) = issue {
+                        if issues.len() > 1 && multiple_issues == MultipleIssuePolicy::Refuse {
+                            return Err(FromCommitsErrorKind::MultipleIssues {
+                                commit: commit.id(),
+                                summary: commit.summary().unwrap_or_default().to_owned(),
+                                issues,
+                            })?;
+                        }
+                        if let Some(issue) = issues.into_iter().next() {
                             return Ok(Some((issue.into(), commit)));
                         }
                     }
 
+                    // If:
+                    // - we're grouping commits by issue, and
+                    // - we're grouping commits without an issue by path or scope,
+                    // then add this commit to its inferred group, if it has one.
+                    if commit_grouping == CommitGrouping::ByIssue {
+                        if let Some(name) = inferred_group(&commit)? {
+                            return Ok(Some((name.into(), commit)));
+                        }
+                    }
+
                     // If:
                     // - we're treating every commit separately, or
                     // - we're considering all commits (even commits without an issue),
@@ -130,11 +286,9 @@ impl<'repo> IssueGroupMap<'repo> {
                         let summary: GitCommitSummary = (&commit).try_into()?;
                         let mut proposed_issue_group = summary.clone();
 
-                        // Use unique issue group names so each commit is
-                        // addressable in the selection menu.
-                        // DISCUSS: would it be better to use an array?
-                        // No, because there's so much ambiguity. Should we expose the
-                        // commit hash? Probably
+                        // Use unique issue group names so each commit gets
+                        // its own branch. The selection menu tells commits
+                        // with the same summary apart by their hashes.
                         while seen_issue_groups.contains(&proposed_issue_group) {
                             suffix += 1;
                             proposed_issue_group = GitCommitSummary(format!("{summary}_{suffix}"));
@@ -167,33 +321,151 @@ impl<'repo> IssueGroupMap<'repo> {
                 },
             )?;
 
+        for (fixup, target) in fixups {
+            match commits_by_issue
+                .values_mut()
+                .find(|commits| commits.iter().any(|commit| commit.id() == target))
+            {
+                Some(commits) => commits.push(fixup),
+                None => writeln!(
+                    io::stderr(),
+                    "Warning: ignoring fixup commit for an ignored commit: {:?}",
+                    fixup.id()
+                )?,
+            }
+        }
+
         Ok(Self(commits_by_issue))
     }
 
+    /// The commits of every group named by `issue`.
+    fn named_commits(&self, issue: &str) -> Result<Vec<Oid>, SelectIssuesError> {
+        let commits: Vec<Oid> = self
+            .iter()
+            .filter(|(issue_group, _commits)| issue_group.is_named_by(issue))
+            .flat_map(|(_issue_group, commits)| commits.iter().map(Commit::id))
+            .collect();
+        match commits.is_empty() {
+            true => Err(SelectIssuesErrorKind::UnknownIssue(issue.to_owned()))?,
+            false => Ok(commits),
+        }
+    }
+
+    /// The commit `rev` resolves to, with its fixup commits.
+    fn revision_commits(
+        &self,
+        rev: &str,
+        repo: &git2::Repository,
+    ) -> Result<Vec<Oid>, SelectIssuesError> {
+        let unknown = || SelectIssuesErrorKind::UnknownCommit(rev.to_owned());
+        let oid = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| unknown())?
+            .id();
+        self.iter()
+            .flat_map(|(_issue_group, commits)| autosquash::fold(commits.iter().cloned()))
+            .find(|squashed| squashed.commits().any(|commit| commit.id() == oid))
+            .map(|squashed| squashed.commits().map(Commit::id).collect())
+            .ok_or_else(|| unknown().into())
+    }
+
+    /// The commits `selection` selects: the commits of its issues and its
+    /// commits, or every commit when it names neither, less the commits of
+    /// its excluded issues.
+    ///
+    /// Every issue and commit must be known, so a typo cannot silently
+    /// select nothing.
+    fn whitelist(
+        &self,
+        selection: &Selection,
+        repo: &git2::Repository,
+    ) -> Result<HashSet<Oid>, SelectIssuesError> {
+        let mut whitelist: HashSet<Oid> = HashSet::new();
+        for issue in &selection.issues {
+            whitelist.extend(self.named_commits(issue)?);
+        }
+        for rev in &selection.commits {
+            whitelist.extend(self.revision_commits(rev, repo)?);
+        }
+        if selection.issues.is_empty() && selection.commits.is_empty() {
+            whitelist.extend(
+                self.iter()
+                    .flat_map(|(_issue_group, commits)| commits.iter().map(Commit::id)),
+            );
+        }
+        for issue in &selection.excluded_issues {
+            for oid in self.named_commits(issue)? {
+                whitelist.remove(&oid);
+            }
+        }
+        Ok(whitelist)
+    }
+
+    /// Keep only the commits in `whitelist`, and the issue groups with at
+    /// least one such commit.
+    fn retain_commits(self, whitelist: &HashSet<Oid>) -> Self {
+        self.into_iter()
+            .map(|(issue_group, commits)| {
+                let commits: Vec<_> = commits
+                    .into_iter()
+                    .filter(|commit| whitelist.contains(&commit.id()))
+                    .collect();
+                (issue_group, commits)
+            })
+            .filter(|(_issue_group, commits)| !commits.is_empty())
+            .collect()
+    }
+
     pub fn select_issues(
         self,
         choose: PromptUserToChooseCommits,
         overlay: OverlayCommitsIntoOnePullRequest,
+        selection: &Selection,
+        repo: &git2::Repository,
     ) -> Result<Self, SelectIssuesError> {
-        let selected_issue_groups: IssueGroupWhitelist = {
+        // Narrow the groups to the selection on the command line first, so
+        // the menu only offers selected commits
+        let this = match selection.is_empty() {
+            true => self,
+            false => {
+                let whitelist = self.whitelist(selection, repo)?;
+                self.retain_commits(&whitelist)
+            }
+        };
+
+        let selected_commits: CommitWhitelist = {
             if choose == PromptUserToChooseCommits::No
-                && overlay == OverlayCommitsIntoOnePullRequest::No
+                && (overlay == OverlayCommitsIntoOnePullRequest::No || !selection.is_empty())
             {
-                IssueGroupWhitelist::WhitelistDNE
+                CommitWhitelist::WhitelistDNE
             } else {
-                let keys = self.0.keys();
-                IssueGroupWhitelist::Whitelist(prompt_user(keys)?)
+                // Offer each commit with its fixup commits, so a fixup is
+                // never published without the commit it fixes
+                let squashed: Vec<(&IssueGroup, Vec<_>)> = this
+                    .iter()
+                    .map(|(issue_group, commits)| {
+                        (issue_group, autosquash::fold(commits.iter().cloned()))
+                    })
+                    .collect();
+                let choices = squashed
+                    .iter()
+                    .flat_map(|(issue_group, commits)| {
+                        commits
+                            .iter()
+                            .map(|commit| CommitChoice::new(issue_group, commit, repo))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                CommitWhitelist::Whitelist(prompt_user(choices)?)
             }
         };
 
-        Ok(match &selected_issue_groups {
-            // If there is a whitelist, only operate on issue_groups in the whitelist
-            IssueGroupWhitelist::Whitelist(whitelist) => self
-                .into_iter()
-                .filter(|(issue_group, _commits)| whitelist.contains(issue_group))
-                .collect(),
+        Ok(match &selected_commits {
+            // If there is a whitelist, only operate on commits in the
+            // whitelist, and on issue groups with at least one such commit
+            CommitWhitelist::Whitelist(whitelist) => this.retain_commits(whitelist),
             // If there is no whitelist, then operate on every issue
-            IssueGroupWhitelist::WhitelistDNE => self,
+            CommitWhitelist::WhitelistDNE => this,
         })
     }
 
@@ -218,4 +490,57 @@ impl<'repo> IssueGroupMap<'repo> {
             OverlayCommitsIntoOnePullRequest::No => self,
         }
     }
+
+    /// Combine the groups named in each of `combinations`, a comma-separated
+    /// list such as `AB-1,AB-2`, into one group per combination.
+    ///
+    /// A combined group takes the place of the earliest group in it, and
+    /// keeps its commits in history order.
+    pub fn apply_combinations(
+        mut self,
+        combinations: &[String],
+        history: &HistoryOrder,
+    ) -> Result<Self, SelectIssuesError> {
+        for combination in combinations {
+            let mut members: Vec<IssueGroup> = Vec::new();
+            let mut commits: Vec<Commit<'repo>> = Vec::new();
+            let mut position = usize::MAX;
+            for value in combination
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+            {
+                let already_combined = members.iter().any(|member| member.is_named_by(value))
+                    || self.0.keys().any(|issue_group| {
+                        matches!(issue_group, IssueGroup::Combined(_))
+                            && issue_group.is_named_by(value)
+                    });
+                if already_combined {
+                    return Err(SelectIssuesErrorKind::CombinedTwice(value.to_owned()))?;
+                }
+                let index = self
+                    .0
+                    .keys()
+                    .position(|issue_group| issue_group.is_named_by(value))
+                    .ok_or_else(|| SelectIssuesErrorKind::UnknownIssue(value.to_owned()))?;
+                let (issue_group, mut group_commits) = self
+                    .0
+                    .shift_remove_index(index)
+                    .expect("index was just found");
+                // Removing a group shifts the ones after it, not the ones
+                // before it, so the earliest index stays valid
+                position = position.min(index);
+                members.push(issue_group);
+                commits.append(&mut group_commits);
+            }
+            commits.sort_by_key(|commit| history.key(commit));
+            let issue_group = match members.len() {
+                0 => continue,
+                1 => members.remove(0),
+                _ => IssueGroup::Combined(members),
+            };
+            self.0.shift_insert(position, issue_group, commits);
+        }
+        Ok(self)
+    }
 }
@@ -1,9 +1,14 @@
 #![forbid(unsafe_code)]
 
+pub mod auto_combine;
+pub mod autosquash;
 pub mod branch_name;
+pub mod branch_template;
 pub mod cli;
+pub mod config;
 #[doc(hidden)]
 pub mod default_branch;
+pub mod dependency;
 pub mod disjoint_branch;
 #[doc(hidden)]
 pub mod editor;
@@ -15,16 +20,23 @@ pub mod fill;
 #[doc(hidden)]
 pub mod git2_repository;
 #[doc(hidden)]
+pub mod github_issue;
+#[doc(hidden)]
 pub mod github_repository_metadata;
 #[doc(hidden)]
+pub mod github_user;
+#[doc(hidden)]
 pub mod interact;
 pub mod issue;
 pub mod issue_group;
 pub mod issue_group_map;
 #[doc(hidden)]
 pub mod little_anyhow;
+pub mod local_commit;
 #[doc(hidden)]
 pub mod log_file;
+pub mod path_group;
+pub mod plan;
 pub mod pre_validation;
 #[doc(hidden)]
 pub mod pull_request;
@@ -32,4 +44,8 @@ pub mod pull_request;
 pub mod pull_request_message;
 #[doc(hidden)]
 pub mod pull_request_metadata;
+pub mod regroup;
+pub mod scope_group;
+pub mod stack;
 pub mod token;
+pub mod tracker_url;
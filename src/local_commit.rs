@@ -0,0 +1,85 @@
+//! Local-only commits, which are held back and never published.
+//!
+//! A commit is local when it has a `Disjoint: local` trailer, or when its
+//! subject matches one of the `disjoint.localSubjectPattern` regexes.
+
+use std::fmt::Write;
+
+use git2::Commit;
+use regex::Regex;
+
+use crate::{autosquash::FixupSubject, issue::IssuePatterns};
+
+/// Trailer key marking how git-disjoint should treat a commit.
+const DISJOINT_TRAILER: &str = "Disjoint";
+
+/// Value of the `Disjoint:` trailer marking a commit as local-only.
+const LOCAL: &str = "local";
+
+#[derive(Clone, Debug, Default)]
+pub struct LocalCommitPatterns {
+    subject_patterns: Vec<Regex>,
+}
+
+impl FromIterator<Regex> for LocalCommitPatterns {
+    fn from_iter<T: IntoIterator<Item = Regex>>(iter: T) -> Self {
+        Self {
+            subject_patterns: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl LocalCommitPatterns {
+    pub fn is_local(&self, commit: &Commit, issue_patterns: &IssuePatterns) -> bool {
+        let summary = commit.summary().unwrap_or_default();
+        self.subject_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(summary))
+            || issue_patterns
+                .trailers(commit.message().unwrap_or_default())
+                .iter()
+                .any(|trailer| {
+                    trailer.key == DISJOINT_TRAILER && trailer.value.eq_ignore_ascii_case(LOCAL)
+                })
+    }
+
+    /// Split `commits` into the commits to publish and the local commits to
+    /// hold back.
+    ///
+    /// Fixup commits for a local commit are held back with it.
+    pub fn partition<'repo>(
+        &self,
+        commits: impl IntoIterator<Item = Commit<'repo>>,
+        issue_patterns: &IssuePatterns,
+    ) -> (Vec<Commit<'repo>>, Vec<Commit<'repo>>) {
+        let mut seen: Vec<Commit<'repo>> = Vec::new();
+        let mut published = Vec::new();
+        let mut held_back: Vec<Commit<'repo>> = Vec::new();
+        for commit in commits {
+            let fixes_local_commit = commit
+                .summary()
+                .and_then(FixupSubject::parse)
+                .and_then(|subject| subject.find_target(&seen))
+                .is_some_and(|target| held_back.iter().any(|local| local.id() == target.id()));
+            seen.push(commit.clone());
+            match fixes_local_commit || self.is_local(&commit, issue_patterns) {
+                true => held_back.push(commit),
+                false => published.push(commit),
+            }
+        }
+        (published, held_back)
+    }
+}
+
+/// List the commits that were held back, oldest first.
+pub fn render_held_back(held_back: &[Commit]) -> String {
+    let mut output = String::new();
+    if held_back.is_empty() {
+        return output;
+    }
+    writeln!(output, "held back (local only):").unwrap();
+    for commit in held_back {
+        writeln!(output, "  * {}", commit.summary().unwrap_or_default()).unwrap();
+    }
+    output
+}
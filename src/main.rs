@@ -1,29 +1,47 @@
 #![forbid(unsafe_code)]
 
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::{mpsc, LazyLock};
 use std::thread::{self, ScopedJoinHandle};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use clap::Parser;
 use git2::Commit;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
+use git_disjoint::auto_combine;
+use git_disjoint::autosquash::SquashedCommit;
 use git_disjoint::branch_name::BranchName;
-use git_disjoint::cli::Cli;
+use git_disjoint::branch_template::{self, BranchNaming, Placeholder};
+use git_disjoint::cli::{
+    Cli, CombineDependentGroups, Command, RegroupCommits, StackDependentGroups,
+};
+use git_disjoint::config::Config;
 use git_disjoint::default_branch::DefaultBranch;
+use git_disjoint::dependency;
 use git_disjoint::disjoint_branch::{DisjointBranch, DisjointBranchMap};
 use git_disjoint::editor::interactive_get_pr_metadata;
 use git_disjoint::error::Error;
 use git_disjoint::execute::execute;
-use git_disjoint::fill;
+use git_disjoint::git2_repository::{self, HistoryOrder};
+use git_disjoint::github_issue::GithubIssue;
 use git_disjoint::github_repository_metadata::GithubRepositoryMetadata;
+use git_disjoint::github_user::GithubUser;
+use git_disjoint::interact;
+use git_disjoint::issue::Issue;
 use git_disjoint::issue_group::IssueGroup;
-use git_disjoint::issue_group_map::IssueGroupMap;
+use git_disjoint::issue_group_map::{IssueGroupMap, Selection};
+use git_disjoint::local_commit;
 use git_disjoint::log_file::LogFile;
+use git_disjoint::plan::{Plan, PlannedBranch};
 use git_disjoint::pre_validation;
 use git_disjoint::pull_request::PullRequest;
 use git_disjoint::pull_request_metadata::PullRequestMetadata;
+use git_disjoint::regroup;
+use git_disjoint::stack;
 use git_disjoint::token;
+use git_disjoint::tracker_url::TrackerUrls;
 
 // DISCUSS: how to handle cherry-pick merge conflicts, and resuming gracefully
 // What if we stored a log of what we were going to do before we took any action?
@@ -44,22 +62,22 @@ static STYLE_COMMIT_WORKING: LazyLock<ProgressStyle> =
 
 #[derive(Debug)]
 struct CommitWork<'repo> {
-    commit: Commit<'repo>,
+    commit: SquashedCommit<'repo>,
     progress_bar: ProgressBar,
 }
 
 impl<'repo> From<&'repo CommitWork<'repo>> for &'repo Commit<'repo> {
     fn from(val: &'repo CommitWork<'repo>) -> Self {
-        &val.commit
+        &val.commit.commit
     }
 }
 
-impl<'repo> From<Commit<'repo>> for CommitWork<'repo> {
-    fn from(commit: Commit<'repo>) -> Self {
+impl<'repo> From<SquashedCommit<'repo>> for CommitWork<'repo> {
+    fn from(commit: SquashedCommit<'repo>) -> Self {
         let progress_bar = ProgressBar::new(1)
             .with_style(STYLE_COMMIT_STABLE.clone())
             .with_prefix(PREFIX_PENDING)
-            .with_message(commit.summary().unwrap().to_string());
+            .with_message(commit.commit.summary().unwrap().to_string());
         Self {
             commit,
             progress_bar,
@@ -69,21 +87,43 @@ impl<'repo> From<Commit<'repo>> for CommitWork<'repo> {
 
 #[derive(Debug)]
 struct WorkOrder<'repo> {
+    issue_group: IssueGroup,
+    github_issue: Option<GithubIssue>,
     branch_name: BranchName,
+    stacked_on: Option<BranchName>,
+    depends_on: Vec<BranchName>,
+    /// The stack this branch is part of, bottom first.
+    stack: Vec<BranchName>,
+    /// The PR's title and body from a plan, used as written.
+    pr_metadata: Option<PullRequestMetadata>,
+    labels: Vec<String>,
+    milestone: Option<u64>,
     commit_work: Vec<CommitWork<'repo>>,
     progress_bar: ProgressBar,
 }
 
-impl<'repo> From<(IssueGroup, DisjointBranch<'repo>)> for WorkOrder<'repo> {
-    fn from((issue_group, commit_plan): (IssueGroup, DisjointBranch<'repo>)) -> Self {
+impl<'repo> WorkOrder<'repo> {
+    fn new(
+        issue_group: IssueGroup,
+        commit_plan: DisjointBranch<'repo>,
+        tracker_urls: &TrackerUrls,
+    ) -> Self {
         let num_commits: u64 = commit_plan.commits.len().try_into().unwrap();
         let progress_bar = ProgressBar::new(num_commits)
             .with_style(STYLE_ISSUE_GROUP_STABLE.clone())
             .with_prefix(PREFIX_PENDING)
-            .with_message(format!("{issue_group}"));
+            .with_message(tracker_urls.render_issue_group(&issue_group));
         // REFACTOR: using into
         WorkOrder {
+            issue_group,
+            github_issue: None,
             branch_name: commit_plan.branch_name,
+            stacked_on: commit_plan.stacked_on,
+            depends_on: commit_plan.depends_on,
+            stack: Vec::new(),
+            pr_metadata: None,
+            labels: Vec::new(),
+            milestone: None,
             // REFACTOR:
             commit_work: commit_plan
                 .commits
@@ -100,6 +140,124 @@ fn cherry_pick(commit: String, log_file: LogFile) -> Result<(), Error> {
         .map_err(|err| Error::cherry_pick(err, commit))
 }
 
+/// Record that the commit at HEAD was picked from `commit`, so it can be
+/// recognized after `commit` is amended.
+fn record_source(commit: String, log_file: LogFile) -> Result<(), Error> {
+    let notes_ref = format!("--ref={}", git2_repository::SOURCE_NOTES_REF);
+    execute(
+        &[
+            "git",
+            "notes",
+            &notes_ref,
+            "add",
+            "--force",
+            "--message",
+            &commit,
+            "HEAD",
+        ],
+        &log_file,
+    )?;
+    Ok(())
+}
+
+/// Fold fixup commits into the commit at HEAD, then reword it to `message`.
+fn autosquash(fixups: Vec<String>, message: String, log_file: LogFile) -> Result<(), Error> {
+    if fixups.is_empty() {
+        return Ok(());
+    }
+    for fixup in fixups {
+        execute(&["git", "cherry-pick", "--no-commit", &fixup], &log_file)
+            .map_err(|err| Error::cherry_pick(err, fixup))?;
+    }
+    execute(
+        &[
+            "git",
+            "commit",
+            "--amend",
+            "--allow-empty",
+            "--message",
+            &message,
+        ],
+        &log_file,
+    )?;
+    Ok(())
+}
+
+/// The title and body of a work order's pull request.
+///
+/// Only multi-commit PRs, whose title and body are ambiguous, are edited in an
+/// editor opened in `editor_root`. Without one, they are drafted from the
+/// commits' subjects instead.
+fn pull_request_metadata(
+    work_order: &WorkOrder,
+    editor_root: Option<&Path>,
+    dependencies: &[String],
+    config: &Config,
+) -> Result<PullRequestMetadata, Error> {
+    let issue_title = work_order
+        .github_issue
+        .as_ref()
+        .map(|github_issue| github_issue.title.clone());
+
+    let mut pr_metadata = match (work_order.commit_work.as_slice(), editor_root) {
+        ([commit_work], _) => {
+            // Fill lines only for single-commit PRs. The multi-commit path
+            // opens an editor where the user controls formatting.
+            PullRequestMetadata::from_commit_message(&commit_work.commit.message(), issue_title)?
+        }
+        (_, Some(root)) => interactive_get_pr_metadata(root, issue_title, &work_order.commit_work)?,
+        (commit_work, None) => {
+            let summaries: Vec<&str> = commit_work
+                .iter()
+                .map(|work| work.commit.commit.summary().unwrap_or_default())
+                .collect();
+            PullRequestMetadata {
+                title: issue_title.unwrap_or_else(|| summaries[0].to_string()),
+                body: summaries
+                    .iter()
+                    .map(|summary| format!("- {summary}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            }
+        }
+    };
+    pr_metadata.append_stack(&work_order.stack, &work_order.branch_name);
+    pr_metadata.append_dependencies(dependencies);
+    // End the body with a normalized footer covering every issue in the
+    // group, so GitHub links each one to this PR, and closes the ones the
+    // commits close.
+    pr_metadata.append_footer(
+        &work_order.issue_group.references(
+            &config.issue_patterns,
+            work_order
+                .commit_work
+                .iter()
+                .flat_map(|work| work.commit.commits()),
+        ),
+        &config.tracker_urls,
+    );
+    // Lead the title with the issue key of projects that have a tracker URL,
+    // so the tracker can find this PR.
+    if let IssueGroup::Issue(issue @ Issue::WorkTracker(key)) = &work_order.issue_group {
+        if config.tracker_urls.url(issue).is_some() {
+            pr_metadata.prefix_title(key);
+        }
+    }
+    // Lead the title of a combined PR with every issue it covers
+    if let IssueGroup::Combined(_) = &work_order.issue_group {
+        let issues = work_order
+            .issue_group
+            .issues()
+            .iter()
+            .map(|issue| issue.reference())
+            .collect::<Vec<_>>();
+        if !issues.is_empty() {
+            pr_metadata.prefix_title(&issues.join(", "));
+        }
+    }
+    Ok(pr_metadata)
+}
+
 fn update_spinner(receiver: mpsc::Receiver<bool>, progress_bar: ProgressBar) -> Result<(), Error> {
     let mut keep_going = true;
     while keep_going {
@@ -126,26 +284,49 @@ fn sleep(duration: Duration) -> Result<(), Error> {
 fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
     thread::scope(|s| {
         let Cli {
+            command,
             all,
+            auto_combine,
             base: _,
             choose,
+            combine,
+            commits: selected_commits,
             // REFACTOR: use an enum
             dry_run,
+            excluded_issues,
             github_token: explicit_token,
+            group_by,
+            issues,
+            multiple_issues,
             overlay,
             ready,
+            regroup,
             separate,
+            stack,
+            yes,
         } = cli;
 
+        let selection = Selection {
+            issues,
+            excluded_issues,
+            commits: selected_commits,
+        };
+
+        // A plan names its own base, and its own branches
+        let plan = match &command {
+            Some(Command::Apply { plan }) => Some(Plan::read(plan)?),
+            Some(Command::Plan { .. }) | None => None,
+        };
+
         let repository_metadata = GithubRepositoryMetadata::try_default()?;
         let github_token = match explicit_token {
             Some(token) => token,
             None => token::resolve_token_from_gh_cli(&repository_metadata.hostname)?,
         };
-        let base_branch = cli.base.clone();
-        let base_branch = match base_branch {
-            Some(base) => DefaultBranch(base),
-            None => DefaultBranch::try_get_default(&repository_metadata, &github_token)?,
+        let base_branch = match (&plan, cli.base.clone()) {
+            (Some(plan), _) => DefaultBranch(plan.base.clone()),
+            (None, Some(base)) => DefaultBranch(base),
+            (None, None) => DefaultBranch::try_get_default(&repository_metadata, &github_token)?,
         };
 
         let GithubRepositoryMetadata {
@@ -158,38 +339,268 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
             repository,
         } = repository_metadata;
 
-        let base_commit = repository.base_commit(&base_branch)?;
-        let commits = repository.commits_since_base(&base_commit)?;
-        // We have to make a first pass to determine the issue groups in play
-        let commits_by_issue_group = IssueGroupMap::try_from_commits(commits, all, separate)?
-            // Now filter the set of all issue groups to just the whitelisted issue groups
-            .select_issues(choose, overlay)?
-            .apply_overlay(overlay);
+        let config = Config::try_from(&repository.config()?)?;
 
-        let commit_plan_by_issue_group: DisjointBranchMap = commits_by_issue_group.try_into()?;
+        let http_client = reqwest::blocking::Client::new();
+
+        let base_commit = match &plan {
+            Some(plan) => plan.base_commit(&repository)?,
+            None => repository.base_commit(&base_branch)?,
+        };
+        let (commit_plan_by_issue_group, mut github_issues) = match &plan {
+            // Check that every planned commit exists, and that the plan
+            // builds no branch on itself
+            Some(plan) => {
+                let commit_plan_by_issue_group = plan.to_branch_map(&repository)?;
+                dependency::check_cycles(&commit_plan_by_issue_group)?;
+                (commit_plan_by_issue_group, HashMap::new())
+            }
+            None => {
+                // Skip commits whose changes already landed upstream
+                let (commits, upstream) = repository.commits_since_base(&base_commit)?;
+                eprint!("{}", git2_repository::render_already_upstream(&upstream));
+                // Order combined and stacked commits as they were walked
+                let history: HistoryOrder = commits.iter().collect();
+                // Hold back local-only commits before grouping, so no grouping
+                // mode can publish them
+                let (commits, held_back) = config
+                    .local_commit_patterns
+                    .partition(commits, &config.issue_patterns);
+                eprint!("{}", local_commit::render_held_back(&held_back));
+                // We have to make a first pass to determine the issue groups in play
+                let commits_by_issue_group = IssueGroupMap::try_from_commits(
+                    commits,
+                    &config,
+                    all,
+                    separate,
+                    multiple_issues,
+                    group_by,
+                    &repository,
+                )?
+                // Now filter the set of all issue groups to just the whitelisted issue groups
+                .select_issues(choose, overlay, &selection, &repository)?
+                // Combine the groups named together on the command line
+                .apply_combinations(&combine, &history)?
+                .apply_overlay(overlay);
+
+                // Combine groups whose commits depend on each other, before
+                // naming branches after them
+                let commits_by_issue_group = match auto_combine {
+                    CombineDependentGroups::Yes => {
+                        let (commits_by_issue_group, combine_report) = auto_combine::combine(
+                            commits_by_issue_group,
+                            &base_commit,
+                            &history,
+                            &repository,
+                        );
+                        if !combine_report.is_empty() {
+                            use std::io::IsTerminal;
+                            let use_color = std::io::stderr().is_terminal();
+                            eprint!("{}", combine_report.render(use_color));
+                        }
+                        commits_by_issue_group
+                    }
+                    CombineDependentGroups::No => commits_by_issue_group,
+                };
+
+                // Look up GitHub issues to name their branches and pull requests
+                let mut github_issues: HashMap<IssueGroup, GithubIssue> = HashMap::new();
+                for (issue_group, _commits) in commits_by_issue_group.iter() {
+                    if let IssueGroup::Issue(Issue::GitHub(number)) = issue_group {
+                        match GithubIssue::get(&http_client, &owner, &name, number, &github_token) {
+                            Ok(github_issue) => {
+                                github_issues.insert(issue_group.clone(), github_issue);
+                            }
+                            Err(err) => {
+                                eprintln!(
+                                    "Warning: unable to look up GitHub issue #{number}: {err}"
+                                )
+                            }
+                        }
+                    }
+                }
+                let titles: HashMap<IssueGroup, String> = github_issues
+                    .iter()
+                    .map(|(issue_group, github_issue)| {
+                        (issue_group.clone(), github_issue.title.clone())
+                    })
+                    .collect();
+
+                // Fill branch name templates, asking GitHub for the user's
+                // login only when the template uses it. Like an issue lookup,
+                // a failed lookup only leaves its placeholder empty
+                let template = config.branch_template.clone();
+                let user = match &template {
+                    Some(template) if template.contains(Placeholder::User) => {
+                        match GithubUser::get(&http_client, &github_token) {
+                            Ok(github_user) => github_user.login,
+                            Err(err) => {
+                                eprintln!("Warning: unable to look up your GitHub login: {err}");
+                                String::new()
+                            }
+                        }
+                    }
+                    _ => String::new(),
+                };
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                let naming = BranchNaming {
+                    template,
+                    max_length: config.branch_max_length,
+                    user,
+                    date: branch_template::date(i64::try_from(now).unwrap_or(i64::MAX)),
+                };
+
+                let commit_plan_by_issue_group = DisjointBranchMap::try_from_issue_group_map(
+                    commits_by_issue_group,
+                    &titles,
+                    &naming,
+                )?;
 
-        // Pre-validate: simulate every cherry-pick in memory before any git writes
+                // Let the user regroup commits, before stacking branches on
+                // the branches they depend on
+                let commit_plan_by_issue_group = match regroup {
+                    RegroupCommits::Yes => {
+                        regroup::edit(commit_plan_by_issue_group, &base_commit, &repository)?
+                    }
+                    RegroupCommits::No => commit_plan_by_issue_group,
+                };
+
+                // Stack branches on the branches they depend on
+                let commit_plan_by_issue_group = match stack {
+                    StackDependentGroups::Yes => {
+                        let (commit_plan_by_issue_group, stack_report) = stack::stack(
+                            commit_plan_by_issue_group,
+                            &base_commit,
+                            &history,
+                            &repository,
+                        );
+                        if !stack_report.is_empty() {
+                            use std::io::IsTerminal;
+                            let use_color = std::io::stderr().is_terminal();
+                            eprint!("{}", stack_report.render(use_color));
+                        }
+                        commit_plan_by_issue_group
+                    }
+                    StackDependentGroups::No => commit_plan_by_issue_group,
+                };
+                // Build branches on the branches their `Depends-On:` trailers
+                // name. Cycles and unselected dependencies are reported before
+                // any git writes
+                let commit_plan_by_issue_group =
+                    dependency::apply(commit_plan_by_issue_group, &config.issue_patterns)?;
+
+                // Warn about published commits that may rely on held-back
+                // commits
+                let held_back_report = pre_validation::held_back_dependencies(
+                    &commit_plan_by_issue_group,
+                    &held_back,
+                    &repository,
+                );
+                if !held_back_report.is_empty() {
+                    use std::io::IsTerminal;
+                    let use_color = std::io::stderr().is_terminal();
+                    eprint!("{}", held_back_report.render(use_color));
+                }
+                (commit_plan_by_issue_group, github_issues)
+            }
+        };
+        let stacks: HashMap<BranchName, Vec<BranchName>> = commit_plan_by_issue_group
+            .iter()
+            .map(|(_issue_group, branch)| {
+                let branch_name = branch.branch_name.clone();
+                let stack = stack::stack_of(&commit_plan_by_issue_group, &branch_name);
+                (branch_name, stack)
+            })
+            .collect();
+
+        // Pre-validate: simulate every cherry-pick in memory before any git
+        // writes
         if let Err(report) =
             pre_validation::validate(&commit_plan_by_issue_group, &base_commit, &repository)
         {
             use std::io::IsTerminal;
             let use_color = std::io::stderr().is_terminal();
-            eprint!("{}", report.render(use_color));
+            eprint!("{}", report.render(use_color, overlay, auto_combine, stack));
             return Err(Error::pre_validation());
         }
 
         let work_orders: Vec<WorkOrder> = commit_plan_by_issue_group
             .into_iter()
-            .map(WorkOrder::from)
+            .map(|(issue_group, commit_plan)| {
+                WorkOrder::new(issue_group, commit_plan, &config.tracker_urls)
+            })
+            .map(|mut work_order| {
+                work_order.github_issue = github_issues.remove(&work_order.issue_group);
+                if let Some(github_issue) = &work_order.github_issue {
+                    work_order.labels = github_issue.labels.clone();
+                    work_order.milestone = github_issue.milestone;
+                }
+                if let Some(planned) = plan
+                    .as_ref()
+                    .and_then(|plan| plan.branch(&work_order.branch_name))
+                {
+                    work_order.progress_bar.set_message(planned.group.clone());
+                    work_order.pr_metadata = Some(PullRequestMetadata {
+                        title: planned.title.clone(),
+                        body: planned.body.clone(),
+                    });
+                    work_order.labels = planned.labels.clone();
+                    work_order.milestone = planned.milestone;
+                }
+                work_order.stack = stacks
+                    .get(&work_order.branch_name)
+                    .cloned()
+                    .unwrap_or_default();
+                work_order
+            })
             .collect();
 
+        // Write the plan instead of taking any action
+        if let Some(Command::Plan { output }) = &command {
+            let plan = Plan {
+                base: base_branch.0.clone(),
+                base_commit: base_commit.id().to_string(),
+                branches: work_orders
+                    .iter()
+                    .map(|work_order| {
+                        let pr_metadata = pull_request_metadata(work_order, None, &[], &config)?;
+                        Ok(PlannedBranch {
+                            name: work_order.branch_name.to_string(),
+                            group: config
+                                .tracker_urls
+                                .render_issue_group(&work_order.issue_group),
+                            stacked_on: work_order.stacked_on.as_ref().map(ToString::to_string),
+                            depends_on: work_order
+                                .depends_on
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect(),
+                            commits: work_order
+                                .commit_work
+                                .iter()
+                                .flat_map(|work| work.commit.commits())
+                                .map(|commit| commit.id().to_string())
+                                .collect(),
+                            title: pr_metadata.title,
+                            body: pr_metadata.body,
+                            labels: work_order.labels.clone(),
+                            milestone: work_order.milestone,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?,
+            };
+            plan.write(output)?;
+            eprintln!("Wrote plan to {}", output.display());
+            return Ok(());
+        }
+
         // Short-circuit early if there is no work to do.
         if work_orders.is_empty() {
             return Ok(());
         }
 
-        let http_client = reqwest::blocking::Client::new();
-
         let multi_progress_bar = MultiProgress::new();
 
         for work_order in work_orders.iter() {
@@ -204,8 +615,11 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
             }
         }
 
-        let mut join_handles: Vec<ScopedJoinHandle<'_, Result<(), Error>>> =
+        // Pull requests are created in the background, keyed by branch so
+        // dependent PRs can wait for the numbers of the PRs they depend on
+        let mut join_handles: Vec<(BranchName, ScopedJoinHandle<'_, Result<u64, Error>>)> =
             Vec::with_capacity(work_orders.len());
+        let mut pull_request_numbers: HashMap<BranchName, u64> = HashMap::new();
 
         for work_order in work_orders {
             work_order
@@ -215,24 +629,56 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
             work_order.progress_bar.tick();
 
             let branch_ref = format!("refs/heads/{}", work_order.branch_name);
-            let branch_obj = repository.revparse_single(&branch_ref);
-
-            // If branch already exists, assume we've already handled this ticket
-            // DISCUSS: in the future, we could compare this branch against the desired
-            // commits, and add any missing commits to this branch and then update the remote
-            if branch_obj.is_ok() {
-                eprintln!(
-                    "Warning: a branch named {:?} already exists",
-                    work_order.branch_name
-                );
-                continue;
-            }
 
-            if !dry_run {
+            // If the branch already exists, its PR is already open
+            let published_head = match repository.revparse_single(&branch_ref) {
+                Ok(branch_obj) => Some(branch_obj.peel_to_commit()?),
+                Err(_) => None,
+            };
+            let planned_commits = || work_order.commit_work.iter().map(|work| &work.commit);
+            // Rebuild the branch when it holds an outdated version of a
+            // planned commit, which was amended or reworded since
+            let outdated_commits = match &published_head {
+                Some(head) => repository.outdated_commits(head, &base_commit, planned_commits())?,
+                None => HashSet::new(),
+            };
+            // Otherwise, add only the planned commits the branch does not
+            // hold yet, compared by patch-id
+            let missing_commits = match &published_head {
+                Some(head) if outdated_commits.is_empty() => {
+                    Some(repository.missing_commits(head, &base_commit, planned_commits())?)
+                }
+                Some(_) | None => None,
+            };
+            let is_missing = |commit_work: &CommitWork| {
+                missing_commits
+                    .as_ref()
+                    .is_none_or(|missing| missing.contains(&commit_work.commit.commit.id()))
+            };
+
+            // A new or rebuilt branch starts on top of the branch beneath it
+            // in its stack if there is one
+            let start_commit = match (&missing_commits, dry_run) {
+                (None, false) => Some(match &work_order.stacked_on {
+                    Some(parent) => repository
+                        .revparse_single(&format!("refs/heads/{parent}"))?
+                        .peel_to_commit()?,
+                    None => base_commit.clone(),
+                }),
+                (Some(_), _) | (None, true) => None,
+            };
+
+            if !dry_run
+                && missing_commits
+                    .as_ref()
+                    .is_none_or(|missing| !missing.is_empty())
+            {
                 // Create a branch
-                repository.branch(work_order.branch_name.as_str(), &base_commit, true)?;
+                if let Some(start_commit) = &start_commit {
+                    repository.branch(work_order.branch_name.as_str(), start_commit, true)?;
+                }
 
-                // Check out the new branch
+                // Check out the branch
                 let branch_obj = repository.revparse_single(&branch_ref)?;
                 repository.checkout_tree(&branch_obj, None)?;
                 repository.set_head(&branch_ref)?;
@@ -240,6 +686,16 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
 
             // Cherry-pick commits related to the target issue
             for commit_work in work_order.commit_work.iter() {
+                if !is_missing(commit_work) {
+                    let message = commit_work.progress_bar.message();
+                    commit_work
+                        .progress_bar
+                        .set_message(format!("{message} (already on branch)"));
+                    commit_work.progress_bar.set_prefix(PREFIX_DONE);
+                    commit_work.progress_bar.finish();
+                    continue;
+                }
+
                 commit_work
                     .progress_bar
                     .set_style(STYLE_COMMIT_WORKING.clone());
@@ -255,11 +711,20 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
                     let progress_bar = commit_work.progress_bar.clone();
                     let ui_thread = s.spawn(|| update_spinner(receiver, progress_bar));
 
-                    let commit_hash = commit_work.commit.id().to_string();
+                    let commit_hash = commit_work.commit.commit.id().to_string();
+                    let fixup_hashes: Vec<String> = commit_work
+                        .commit
+                        .fixups
+                        .iter()
+                        .map(|fixup| fixup.commit.id().to_string())
+                        .collect();
+                    let message = commit_work.commit.message();
                     let worker_thread = s.spawn(move || {
                         let result = match dry_run {
                             true => sleep(Duration::from_millis(750)),
-                            false => cherry_pick(commit_hash, log_file),
+                            false => cherry_pick(commit_hash.clone(), log_file.clone())
+                                .and_then(|_| autosquash(fixup_hashes, message, log_file.clone()))
+                                .and_then(|_| record_source(commit_hash, log_file)),
                         };
                         // tell the ui_thread to stop
                         sender
@@ -283,6 +748,99 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
                 commit_work.progress_bar.finish()
             }
 
+            // Update an existing branch, and report how. Pushing it updates
+            // its open PR, so there is no PR to create.
+            if let Some(published_head) = &published_head {
+                let commits = |n: usize| match n {
+                    1 => "1 commit".to_string(),
+                    n => format!("{n} commits"),
+                };
+                let update = match &missing_commits {
+                    None => format!("rebuilt {} amended", commits(outdated_commits.len())),
+                    Some(missing) if missing.is_empty() => "branch is up to date".to_string(),
+                    Some(missing) => format!("added {} to existing branch", commits(missing.len())),
+                };
+                let message = work_order.progress_bar.message();
+                work_order
+                    .progress_bar
+                    .set_message(format!("{message} ({update})"));
+
+                if !dry_run
+                    && missing_commits
+                        .as_ref()
+                        .is_none_or(|missing| !missing.is_empty())
+                {
+                    let mut declined = false;
+                    match (&missing_commits, &start_commit) {
+                        // Show what the rebuild changes, then, once confirmed,
+                        // replace the branch unless someone else pushed to it
+                        // since
+                        (None, Some(new_base)) => {
+                            let new_head = repository.head()?.peel_to_commit()?;
+                            let old_base = repository.find_commit(
+                                repository.merge_base(published_head.id(), new_base.id())?,
+                            )?;
+                            let diff = repository.render_range_diff(
+                                (&old_base, published_head),
+                                (new_base, &new_head),
+                                &log_file,
+                            )?;
+                            let confirmed = multi_progress_bar.suspend(|| {
+                                eprintln!(
+                                    "Rebuilding {} ({:.7} -> {:.7}):\n{diff}",
+                                    work_order.branch_name,
+                                    published_head.id(),
+                                    new_head.id(),
+                                );
+                                match yes {
+                                    true => Ok(true),
+                                    false => interact::confirm_force_push(&work_order.branch_name)
+                                        .map_err(Error::confirm_force_push),
+                                }
+                            })?;
+                            match confirmed {
+                                true => {
+                                    let lease = format!(
+                                        "--force-with-lease={}:{}",
+                                        work_order.branch_name,
+                                        published_head.id()
+                                    );
+                                    execute(
+                                        &[
+                                            "git",
+                                            "push",
+                                            &lease,
+                                            &remote,
+                                            work_order.branch_name.as_str(),
+                                        ],
+                                        &log_file,
+                                    )?;
+                                }
+                                false => declined = true,
+                            }
+                        }
+                        (Some(_), _) | (None, None) => execute(
+                            &["git", "push", &remote, (work_order.branch_name.as_str())],
+                            &log_file,
+                        )?,
+                    }
+                    execute(&["git", "checkout", "-"], &log_file)?;
+                    // Put the branch back as it was published
+                    if declined {
+                        repository.branch(work_order.branch_name.as_str(), published_head, true)?;
+                        work_order
+                            .progress_bar
+                            .set_message(format!("{message} (rebuild declined)"));
+                    }
+                }
+                work_order
+                    .progress_bar
+                    .set_style(STYLE_ISSUE_GROUP_STABLE.clone());
+                work_order.progress_bar.set_prefix(PREFIX_DONE);
+                work_order.progress_bar.finish();
+                continue;
+            }
+
             if !dry_run {
                 // Push the branch
                 execute(
@@ -290,22 +848,34 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
                     &log_file,
                 )?;
 
-                // Open a pull request
-                // Only ask the user to edit the PR metadata when multiple commits
-                // create ambiguity about the contents of the PR title and body.
-                let needs_edit = work_order.commit_work.len() > 1;
-
-                let pr_metadata = match needs_edit {
-                    true => interactive_get_pr_metadata(&root, &work_order.commit_work)?,
-                    false => {
-                        // Fill lines only for single-commit PRs. The multi-commit path
-                        // opens an editor where the user controls formatting.
-                        let commit = &work_order.commit_work.first().unwrap().commit;
-                        let mut pr_metadata: PullRequestMetadata =
-                            commit.message().unwrap().parse()?;
-                        pr_metadata.body = fill::fill_lines(&pr_metadata.body);
+                // Link the PRs this PR depends on, waiting for their numbers
+                let mut dependencies = Vec::with_capacity(work_order.depends_on.len());
+                for dependency in &work_order.depends_on {
+                    if let Some(position) = join_handles
+                        .iter()
+                        .position(|(branch_name, _handle)| branch_name == dependency)
+                    {
+                        let (branch_name, handle) = join_handles.remove(position);
+                        pull_request_numbers.insert(branch_name, handle.join().unwrap()?);
+                    }
+                    dependencies.push(match pull_request_numbers.get(dependency) {
+                        Some(number) => format!("#{number}"),
+                        // The dependency's branch already existed, so no PR
+                        // was created for it in this run
+                        None => format!("`{dependency}`"),
+                    });
+                }
+
+                // Open a pull request, as planned if there is a plan
+                let pr_metadata = match &work_order.pr_metadata {
+                    Some(pr_metadata) => {
+                        let mut pr_metadata = pr_metadata.clone();
+                        pr_metadata.append_dependencies(&dependencies);
                         pr_metadata
                     }
+                    None => {
+                        pull_request_metadata(&work_order, Some(&root), &dependencies, &config)?
+                    }
                 };
 
                 let pull_request = PullRequest {
@@ -316,14 +886,20 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
                     body: pr_metadata.body,
                     github_token: github_token.clone(),
                     branch_name: work_order.branch_name.clone(),
-                    base: base_branch.clone(),
+                    // A stacked PR targets the branch beneath it
+                    base: match &work_order.stacked_on {
+                        Some(parent) => DefaultBranch(parent.to_string()),
+                        None => base_branch.clone(),
+                    },
                     draft: !ready,
+                    labels: work_order.labels.clone(),
+                    milestone: work_order.milestone,
                 };
 
                 let http_client = http_client.clone();
                 let pull_request_join_handle =
                     s.spawn(move || pull_request.create(http_client).map_err(Into::into));
-                join_handles.push(pull_request_join_handle);
+                join_handles.push((work_order.branch_name.clone(), pull_request_join_handle));
 
                 // Finally, check out the original ref
                 execute(&["git", "checkout", "-"], &log_file)?;
@@ -336,7 +912,7 @@ fn do_git_disjoint(cli: Cli, log_file: LogFile) -> Result<(), Error> {
             work_order.progress_bar.finish();
         }
 
-        for handle in join_handles {
+        for (_branch_name, handle) in join_handles {
             handle.join().unwrap()?;
         }
 
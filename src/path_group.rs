@@ -0,0 +1,216 @@
+//! Group commits without an issue by the paths they touch, the way
+//! `--group-by path` does.
+//!
+//! When the repository has a CODEOWNERS file, a commit is grouped by the set
+//! of owners of the paths it touches. Otherwise, it is grouped by the
+//! top-level directories it touches.
+
+use std::{collections::BTreeSet, path::Path};
+
+use git2::Commit;
+use regex::Regex;
+
+use crate::issue_group::GroupName;
+
+/// Where GitHub looks for a CODEOWNERS file, in order.
+const CODE_OWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The group of commits that only touch files at the root of the repository.
+const ROOT_GROUP: &str = "root";
+
+/// The group of commits that only touch files without an owner.
+const UNOWNED_GROUP: &str = "unowned";
+
+/// One CODEOWNERS rule: a path pattern and the owners of matching paths.
+#[derive(Clone, Debug)]
+struct CodeOwnersRule {
+    pattern: Regex,
+    owners: Vec<String>,
+}
+
+/// The rules of a CODEOWNERS file.
+#[derive(Clone, Debug, Default)]
+pub struct CodeOwners {
+    rules: Vec<CodeOwnersRule>,
+}
+
+/// Translate a gitignore-style CODEOWNERS pattern into a regex matching the
+/// paths it owns, including every path under a matching directory.
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    // A pattern with a slash before its end is relative to the root;
+    // otherwise it matches at any depth
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let mut body = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                match chars.peek() {
+                    Some('/') => {
+                        chars.next();
+                        body.push_str("(?:.*/)?");
+                    }
+                    _ => body.push_str(".*"),
+                }
+            }
+            '*' => body.push_str("[^/]*"),
+            '?' => body.push_str("[^/]"),
+            c => body.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    let prefix = match anchored {
+        true => "^",
+        false => "^(?:.*/)?",
+    };
+    let suffix = match dir_only {
+        true => "/.*$",
+        false => "(?:/.*)?$",
+    };
+    Regex::new(&format!("{prefix}{body}{suffix}")).ok()
+}
+
+impl CodeOwners {
+    pub fn parse(contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = pattern_regex(fields.next()?)?;
+                Some(CodeOwnersRule {
+                    pattern,
+                    owners: fields
+                        .take_while(|field| !field.starts_with('#'))
+                        .map(ToOwned::to_owned)
+                        .collect(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of `path`, from the last rule that matches it.
+    pub fn owners(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.pattern.is_match(path))
+            .map_or(&[], |rule| &rule.owners)
+    }
+}
+
+/// Assigns commits to groups by the paths they touch.
+pub struct PathGrouper<'repo> {
+    repo: &'repo git2::Repository,
+    code_owners: Option<CodeOwners>,
+}
+
+impl<'repo> PathGrouper<'repo> {
+    /// Read the CODEOWNERS file at `HEAD`, if there is one.
+    pub fn new(repo: &'repo git2::Repository) -> Result<Self, git2::Error> {
+        let tree = repo.head()?.peel_to_tree()?;
+        let code_owners = CODE_OWNERS_PATHS
+            .iter()
+            .find_map(|path| tree.get_path(Path::new(path)).ok())
+            .map(|entry| entry.to_object(repo)?.peel_to_blob())
+            .transpose()?
+            .map(|blob| CodeOwners::parse(&String::from_utf8_lossy(blob.content())));
+        Ok(Self { repo, code_owners })
+    }
+
+    /// Every path `commit` adds, changes or deletes.
+    fn touched_paths(&self, commit: &Commit) -> Result<BTreeSet<String>, git2::Error> {
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff =
+            self.repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        Ok(diff
+            .deltas()
+            .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+            .flatten()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// The group of `commit`: the owners of the paths it touches, or the
+    /// top-level directories it touches when there is no CODEOWNERS file.
+    pub fn group_name(&self, commit: &Commit) -> Result<GroupName, git2::Error> {
+        let paths = self.touched_paths(commit)?;
+        let (keys, fallback): (BTreeSet<&str>, _) = match &self.code_owners {
+            Some(code_owners) => (
+                paths
+                    .iter()
+                    .flat_map(|path| code_owners.owners(path))
+                    .map(String::as_str)
+                    .collect(),
+                UNOWNED_GROUP,
+            ),
+            None => (
+                paths
+                    .iter()
+                    .filter_map(|path| path.split_once('/'))
+                    .map(|(directory, _rest)| directory)
+                    .collect(),
+                ROOT_GROUP,
+            ),
+        };
+        Ok(GroupName(match keys.is_empty() {
+            true => fallback.to_string(),
+            false => keys.into_iter().collect::<Vec<_>>().join(" "),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CodeOwners;
+
+    const CODE_OWNERS: &str = "\
+# Default owners
+*       @acme/app
+/docs/  @acme/docs   # documentation
+*.tf    @acme/infra
+/ci/**/deploy.yml @acme/infra @acme/release
+";
+
+    fn owners(path: &str) -> Vec<String> {
+        CodeOwners::parse(CODE_OWNERS).owners(path).to_vec()
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        assert_eq!(owners("src/main.rs"), ["@acme/app"]);
+        assert_eq!(owners("docs/guide/intro.md"), ["@acme/docs"]);
+        assert_eq!(owners("infra/network/main.tf"), ["@acme/infra"]);
+    }
+
+    #[test]
+    fn anchored_and_double_star_patterns() {
+        assert_eq!(
+            owners("ci/prod/deploy.yml"),
+            ["@acme/infra", "@acme/release"]
+        );
+        assert_eq!(owners("ci/deploy.yml"), ["@acme/infra", "@acme/release"]);
+        assert_eq!(owners("src/docs/readme.md"), ["@acme/app"]);
+    }
+
+    #[test]
+    fn paths_without_a_rule_have_no_owners() {
+        assert!(CodeOwners::parse("/docs/ @acme/docs")
+            .owners("src/main.rs")
+            .is_empty());
+    }
+}
@@ -0,0 +1,386 @@
+//! Plans written by `git disjoint plan` and executed by `git disjoint apply`.
+//!
+//! A plan lists every branch git-disjoint would create: its commits, the
+//! branch it is built on, and the title and body of its PR. It can be edited
+//! before it is applied.
+
+use std::{collections::HashSet, error::Error, fmt::Display, io, path::Path};
+
+use git2::{Commit, Oid};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autosquash,
+    branch_name::BranchName,
+    disjoint_branch::{DisjointBranch, DisjointBranchMap},
+    issue_group::{GroupName, IssueGroup},
+};
+
+/// Comment written at the top of every plan file.
+const PLAN_HEADER: &str = "\
+# Edit this plan, then run `git disjoint apply` on it to create the branches
+# and pull requests it describes.
+";
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    /// The branch unstacked pull requests target.
+    pub base: String,
+    /// The commit unstacked branches are built on.
+    pub base_commit: String,
+    #[serde(default, rename = "branch")]
+    pub branches: Vec<PlannedBranch>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PlannedBranch {
+    pub name: String,
+    /// The issue group the branch was planned for, shown while applying.
+    pub group: String,
+    /// The planned branch this branch is built on, instead of the base.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stacked_on: Option<String>,
+    /// The planned branches whose pull requests this pull request depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Commits to cherry-pick, oldest first. Fixup commits are folded into
+    /// their target, as in a normal run.
+    pub commits: Vec<String>,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<u64>,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct PlanError {
+    kind: PlanErrorKind,
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            PlanErrorKind::Read(_) => write!(f, "unable to read plan"),
+            PlanErrorKind::Write(_) => write!(f, "unable to write plan"),
+            PlanErrorKind::Parse(_) => write!(f, "unable to parse plan"),
+            PlanErrorKind::Serialize(_) => write!(f, "unable to serialize plan"),
+            PlanErrorKind::UnknownCommit(commit, _) => {
+                write!(
+                    f,
+                    "plan refers to commit {commit:?}, which is not in this repository"
+                )
+            }
+            PlanErrorKind::DuplicateBranch(branch) => {
+                write!(f, "plan lists branch `{branch}` more than once")
+            }
+            PlanErrorKind::InvalidBranchName(branch) => {
+                write!(
+                    f,
+                    "plan lists branch `{branch}`, which is not a valid git branch name"
+                )
+            }
+            PlanErrorKind::EmptyBranch(branch) => {
+                write!(f, "plan lists no commits for branch `{branch}`")
+            }
+            PlanErrorKind::UnknownBranch { branch, reference } => write!(
+                f,
+                "branch `{branch}` refers to branch `{reference}`, which is not in the plan"
+            ),
+        }
+    }
+}
+
+impl Error for PlanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            PlanErrorKind::Read(err) => Some(err),
+            PlanErrorKind::Write(err) => Some(err),
+            PlanErrorKind::Parse(err) => Some(err),
+            PlanErrorKind::Serialize(err) => Some(err),
+            PlanErrorKind::UnknownCommit(_, err) => Some(err),
+            PlanErrorKind::DuplicateBranch(_) => None,
+            PlanErrorKind::InvalidBranchName(_) => None,
+            PlanErrorKind::EmptyBranch(_) => None,
+            PlanErrorKind::UnknownBranch { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PlanErrorKind {
+    #[non_exhaustive]
+    Read(io::Error),
+    #[non_exhaustive]
+    Write(io::Error),
+    #[non_exhaustive]
+    Parse(toml::de::Error),
+    #[non_exhaustive]
+    Serialize(toml::ser::Error),
+    #[non_exhaustive]
+    UnknownCommit(String, git2::Error),
+    #[non_exhaustive]
+    DuplicateBranch(String),
+    #[non_exhaustive]
+    InvalidBranchName(String),
+    #[non_exhaustive]
+    EmptyBranch(String),
+    #[non_exhaustive]
+    UnknownBranch { branch: String, reference: String },
+}
+
+impl From<PlanErrorKind> for PlanError {
+    fn from(kind: PlanErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl Plan {
+    pub fn read(path: &Path) -> Result<Self, PlanError> {
+        let contents = std::fs::read_to_string(path).map_err(PlanErrorKind::Read)?;
+        contents.parse()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), PlanError> {
+        std::fs::write(path, self.to_toml()?).map_err(PlanErrorKind::Write)?;
+        Ok(())
+    }
+
+    fn to_toml(&self) -> Result<String, PlanError> {
+        let toml = toml::to_string_pretty(self).map_err(PlanErrorKind::Serialize)?;
+        Ok(format!("{PLAN_HEADER}\n{toml}"))
+    }
+
+    fn find_commit<'repo>(
+        repo: &'repo git2::Repository,
+        commit: &str,
+    ) -> Result<Commit<'repo>, PlanError> {
+        Oid::from_str(commit)
+            .and_then(|oid| repo.find_commit(oid))
+            .map_err(|err| PlanErrorKind::UnknownCommit(commit.to_owned(), err).into())
+    }
+
+    /// The commit unstacked branches are built on.
+    pub fn base_commit<'repo>(
+        &self,
+        repo: &'repo git2::Repository,
+    ) -> Result<Commit<'repo>, PlanError> {
+        Self::find_commit(repo, &self.base_commit)
+    }
+
+    /// Check the plan against `repo`, and turn it into branches to create.
+    ///
+    /// Every commit must exist, branch names must be unique, valid git branch
+    /// names, and every branch a branch is built on or depends on must be in
+    /// the plan. Nothing is created until the whole plan checks out.
+    pub fn to_branch_map<'repo>(
+        &self,
+        repo: &'repo git2::Repository,
+    ) -> Result<DisjointBranchMap<'repo>, PlanError> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for branch in &self.branches {
+            if !seen.insert(&branch.name) {
+                return Err(PlanErrorKind::DuplicateBranch(branch.name.clone()))?;
+            }
+            if !git2::Branch::name_is_valid(&branch.name).unwrap_or(false) {
+                return Err(PlanErrorKind::InvalidBranchName(branch.name.clone()))?;
+            }
+        }
+        let mut branch_map: DisjointBranchMap = self
+            .branches
+            .iter()
+            .map(|branch| {
+                if branch.commits.is_empty() {
+                    return Err(PlanErrorKind::EmptyBranch(branch.name.clone()))?;
+                }
+                for reference in branch.stacked_on.iter().chain(&branch.depends_on) {
+                    if !seen.contains(reference.as_str()) {
+                        return Err(PlanErrorKind::UnknownBranch {
+                            branch: branch.name.clone(),
+                            reference: reference.clone(),
+                        })?;
+                    }
+                }
+                let commits = branch
+                    .commits
+                    .iter()
+                    .map(|commit| Self::find_commit(repo, commit))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // Branches are keyed by name, which is unique in a plan
+                Ok((
+                    IssueGroup::Named(GroupName(branch.name.clone())),
+                    DisjointBranch {
+                        branch_name: BranchName::new(branch.name.clone()),
+                        commits: autosquash::fold(commits),
+                        stacked_on: branch.stacked_on.clone().map(BranchName::new),
+                        depends_on: branch
+                            .depends_on
+                            .iter()
+                            .cloned()
+                            .map(BranchName::new)
+                            .collect(),
+                    },
+                ))
+            })
+            .collect::<Result<_, PlanError>>()?;
+        branch_map.sort_stacks();
+        Ok(branch_map)
+    }
+
+    /// The planned branch named `branch_name`.
+    pub fn branch(&self, branch_name: &BranchName) -> Option<&PlannedBranch> {
+        self.branches
+            .iter()
+            .find(|branch| &BranchName::new(branch.name.clone()) == branch_name)
+    }
+}
+
+impl std::str::FromStr for Plan {
+    type Err = PlanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(s).map_err(PlanErrorKind::Parse)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+
+    use super::{Plan, PlannedBranch};
+
+    fn plan(base_commit: &str, commit: &str) -> Plan {
+        Plan {
+            base: "main".to_string(),
+            base_commit: base_commit.to_string(),
+            branches: vec![
+                PlannedBranch {
+                    name: "AB-1-add-config".to_string(),
+                    group: "Issue AB-1".to_string(),
+                    stacked_on: None,
+                    depends_on: Vec::new(),
+                    commits: vec![commit.to_string()],
+                    title: "feat: add config".to_string(),
+                    body: "Adds config.\n\nCloses AB-1".to_string(),
+                    labels: vec!["enhancement".to_string()],
+                    milestone: Some(2),
+                },
+                PlannedBranch {
+                    name: "AB-2-use-config".to_string(),
+                    group: "Issue AB-2".to_string(),
+                    stacked_on: Some("AB-1-add-config".to_string()),
+                    depends_on: Vec::new(),
+                    commits: vec![commit.to_string()],
+                    title: "feat: use config".to_string(),
+                    body: String::new(),
+                    labels: Vec::new(),
+                    milestone: None,
+                },
+            ],
+        }
+    }
+
+    /// A repository with a base commit and one commit on top of it.
+    fn repository() -> (TempDir, git2::Repository, String, String) {
+        let tempdir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(tempdir.path()).unwrap();
+        let signature = git2::Signature::now("Test User", "test@test.com").unwrap();
+        let (base, commit) = {
+            let tree = repo
+                .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+                .unwrap();
+            let base = repo
+                .commit(None, &signature, &signature, "base", &tree, &[])
+                .unwrap();
+            let commit = repo
+                .commit(
+                    None,
+                    &signature,
+                    &signature,
+                    "feat: add config",
+                    &tree,
+                    &[&repo.find_commit(base).unwrap()],
+                )
+                .unwrap();
+            (base.to_string(), commit.to_string())
+        };
+        (tempdir, repo, base, commit)
+    }
+
+    #[test]
+    fn plan_round_trips_through_toml() {
+        let plan = plan("0123abc", "4567def");
+        let toml = plan.to_toml().unwrap();
+        assert!(toml.starts_with("# Edit this plan"));
+        assert_eq!(toml.parse::<Plan>().unwrap(), plan);
+    }
+
+    #[test]
+    fn plan_becomes_branch_map() {
+        let (_tempdir, repo, base, commit) = repository();
+        let plan = plan(&base, &commit);
+        let branch_map = plan.to_branch_map(&repo).unwrap();
+        let branches: Vec<_> = branch_map
+            .iter()
+            .map(|(_group, branch)| {
+                (
+                    branch.branch_name.to_string(),
+                    branch.stacked_on.as_ref().map(ToString::to_string),
+                )
+            })
+            .collect();
+        assert_eq!(
+            branches,
+            [
+                ("AB-1-add-config".to_string(), None),
+                (
+                    "AB-2-use-config".to_string(),
+                    Some("AB-1-add-config".to_string())
+                ),
+            ]
+        );
+        assert_eq!(plan.base_commit(&repo).unwrap().id().to_string(), base);
+    }
+
+    #[test]
+    fn unknown_commit_is_an_error() {
+        let (_tempdir, repo, base, _commit) = repository();
+        let plan = plan(&base, "1111111111111111111111111111111111111111");
+        assert!(plan.to_branch_map(&repo).is_err());
+    }
+
+    #[test]
+    fn unknown_stacked_on_branch_is_an_error() {
+        let (_tempdir, repo, base, commit) = repository();
+        let mut plan = plan(&base, &commit);
+        plan.branches[1].stacked_on = Some("renamed".to_string());
+        assert!(plan.to_branch_map(&repo).is_err());
+    }
+
+    #[test]
+    fn duplicate_branch_is_an_error() {
+        let (_tempdir, repo, base, commit) = repository();
+        let mut plan = plan(&base, &commit);
+        plan.branches[1].name = plan.branches[0].name.clone();
+        plan.branches[1].stacked_on = None;
+        assert!(plan.to_branch_map(&repo).is_err());
+    }
+
+    #[test]
+    fn invalid_branch_name_is_an_error() {
+        let (_tempdir, repo, base, commit) = repository();
+        for name in ["my branch", "a..b", "a~1", "a:b", "config.lock"] {
+            let mut plan = plan(&base, &commit);
+            plan.branches[0].name = name.to_string();
+            plan.branches[1].stacked_on = Some(name.to_string());
+            let err = plan.to_branch_map(&repo).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("plan lists branch `{name}`, which is not a valid git branch name")
+            );
+        }
+    }
+}
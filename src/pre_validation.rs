@@ -2,8 +2,11 @@ use std::fmt::Write;
 
 use git2::Commit;
 
+use crate::autosquash::SquashedCommit;
 use crate::branch_name::BranchName;
+use crate::cli::{CombineDependentGroups, OverlayCommitsIntoOnePullRequest, StackDependentGroups};
 use crate::disjoint_branch::DisjointBranchMap;
+use crate::git2_repository::Repository;
 
 #[derive(Debug)]
 pub struct BranchConflict {
@@ -18,7 +21,25 @@ pub struct PreValidationReport {
 }
 
 impl PreValidationReport {
-    pub fn render(&self, _use_color: bool) -> String {
+    /// Render the report, suggesting only the flags that are not already
+    /// active.
+    pub fn render(
+        &self,
+        _use_color: bool,
+        overlay: OverlayCommitsIntoOnePullRequest,
+        auto_combine: CombineDependentGroups,
+        stack: StackDependentGroups,
+    ) -> String {
+        let mut alternatives = Vec::new();
+        if auto_combine == CombineDependentGroups::No {
+            alternatives.push("`--auto-combine` to combine dependent groups into a single PR");
+        }
+        if stack == StackDependentGroups::No {
+            alternatives.push("`--stack` to stack dependent groups as chained PRs");
+        }
+        if overlay == OverlayCommitsIntoOnePullRequest::No {
+            alternatives.push("`--overlay` to combine every group into a single PR");
+        }
         let mut output = String::new();
         for (i, conflict) in self.conflicts.iter().enumerate() {
             if i > 0 {
@@ -46,97 +67,279 @@ impl PreValidationReport {
                 "           into separate branches from the same base"
             )
             .unwrap();
+            match alternatives.as_slice() {
+                [] => writeln!(
+                    output,
+                    "   = help: consider assigning them to the same issue"
+                )
+                .unwrap(),
+                alternatives => {
+                    writeln!(
+                        output,
+                        "   = help: consider assigning them to the same issue, or use one of:"
+                    )
+                    .unwrap();
+                    for alternative in alternatives {
+                        writeln!(output, "           {alternative}").unwrap();
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+/// A published commit that changes a path a held-back commit changed first.
+#[derive(Debug)]
+pub struct HeldBackDependency {
+    pub branch_name: BranchName,
+    pub commit_summary: String,
+    pub held_back_summary: String,
+    pub shared_paths: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HeldBackReport {
+    pub dependencies: Vec<HeldBackDependency>,
+}
+
+impl HeldBackReport {
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    pub fn render(&self, _use_color: bool) -> String {
+        let mut output = String::new();
+        for (i, dependency) in self.dependencies.iter().enumerate() {
+            if i > 0 {
+                writeln!(output).unwrap();
+            }
             writeln!(
                 output,
-                "   = help: consider assigning them to the same issue, or use `--overlay`"
+                "warning: branch `{}` may depend on a held-back commit",
+                dependency.branch_name
             )
             .unwrap();
-            writeln!(output, "           to combine them into a single PR").unwrap();
+            writeln!(output, "  --> commit \"{}\"", dependency.commit_summary).unwrap();
+            writeln!(output, "   |").unwrap();
+            for path in &dependency.shared_paths {
+                writeln!(
+                    output,
+                    "   = held-back commit \"{}\" also changes {}",
+                    dependency.held_back_summary, path
+                )
+                .unwrap();
+            }
+            writeln!(output, "   |").unwrap();
+            writeln!(
+                output,
+                "   = help: the branch is built without the held-back commit, so make"
+            )
+            .unwrap();
+            writeln!(output, "           sure it does not rely on those changes").unwrap();
         }
         output
     }
 }
 
+/// Paths changed by `commit`, relative to its first parent.
+fn changed_paths(repo: &git2::Repository, commit: &Commit) -> Vec<String> {
+    let parent_tree = commit.parent(0).and_then(|parent| parent.tree()).ok();
+    let tree = commit.tree().ok();
+    repo.diff_tree_to_tree(parent_tree.as_ref(), tree.as_ref(), None)
+        .map(|diff| {
+            diff.deltas()
+                .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+                .map(|path| path.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Find published commits that change a path an earlier held-back commit
+/// changed, since they may depend on changes that are never published.
+pub fn held_back_dependencies(
+    branch_map: &DisjointBranchMap,
+    held_back: &[Commit],
+    repo: &git2::Repository,
+) -> HeldBackReport {
+    let held_back: Vec<(&Commit, Vec<String>)> = held_back
+        .iter()
+        .map(|commit| (commit, changed_paths(repo, commit)))
+        .collect();
+    let mut dependencies = Vec::new();
+
+    for (_issue_group, branch) in branch_map.iter() {
+        for commit in branch.commits.iter().flat_map(SquashedCommit::commits) {
+            let paths = changed_paths(repo, commit);
+            for (local, local_paths) in &held_back {
+                if !repo
+                    .graph_descendant_of(commit.id(), local.id())
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+                let shared_paths: Vec<String> = paths
+                    .iter()
+                    .filter(|path| local_paths.contains(path))
+                    .cloned()
+                    .collect();
+                if !shared_paths.is_empty() {
+                    dependencies.push(HeldBackDependency {
+                        branch_name: branch.branch_name.clone(),
+                        commit_summary: commit.summary().unwrap_or("").to_string(),
+                        held_back_summary: local.summary().unwrap_or("").to_string(),
+                        shared_paths,
+                    });
+                }
+            }
+        }
+    }
+
+    HeldBackReport { dependencies }
+}
+
+/// A commit whose cherry-pick would conflict, and the paths in conflict.
+#[derive(Debug)]
+pub struct SimulatedConflict<'repo> {
+    pub commit: Commit<'repo>,
+    pub conflicting_paths: Vec<String>,
+}
+
+impl SimulatedConflict<'_> {
+    /// Whether `commit` could explain this conflict: it is an ancestor of the
+    /// conflicting commit, and changes one of the conflicting paths.
+    pub fn is_caused_by(&self, commit: &Commit, repo: &git2::Repository) -> bool {
+        repo.graph_descendant_of(self.commit.id(), commit.id())
+            .unwrap_or(false)
+            && changed_paths(repo, commit)
+                .iter()
+                .any(|path| self.conflicting_paths.contains(path))
+    }
+}
+
+/// A commit the simulation could not pick, and the step that failed.
+type SimulationError<'repo> = (Commit<'repo>, &'static str);
+
+/// Cherry-pick `commits` onto `base_commit` in memory, stopping at the first
+/// conflict.
+pub fn simulate<'a, 'repo: 'a>(
+    commits: impl IntoIterator<Item = &'a Commit<'repo>>,
+    base_commit: &Commit<'repo>,
+    repo: &'repo git2::Repository,
+) -> Result<Option<SimulatedConflict<'repo>>, SimulationError<'repo>> {
+    let mut simulated_head = base_commit.clone();
+
+    for commit in commits {
+        let mut index = repo
+            .cherrypick_commit(commit, &simulated_head, 0, None)
+            .map_err(|_| (commit.clone(), "(git2 error)"))?;
+
+        if index.has_conflicts() {
+            let conflicting_paths: Vec<String> = index
+                .conflicts()
+                .ok()
+                .into_iter()
+                .flatten()
+                .filter_map(|conflict| {
+                    let conflict = conflict.ok()?;
+                    conflict
+                        .our
+                        .or(conflict.their)
+                        .or(conflict.ancestor)
+                        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+                })
+                .collect();
+
+            return Ok(Some(SimulatedConflict {
+                commit: commit.clone(),
+                conflicting_paths,
+            }));
+        }
+
+        // Advance simulated head
+        let tree_oid = index
+            .write_tree_to(repo)
+            .map_err(|_| (commit.clone(), "(write_tree error)"))?;
+        let tree = repo
+            .find_tree(tree_oid)
+            .map_err(|_| (commit.clone(), "(find_tree error)"))?;
+        let sig = commit.author();
+        let simulated_oid = repo
+            .commit(None, &sig, &sig, "simulated", &tree, &[&simulated_head])
+            .map_err(|_| (commit.clone(), "(commit error)"))?;
+        simulated_head = repo
+            .find_commit(simulated_oid)
+            .map_err(|_| (commit.clone(), "(find_commit error)"))?;
+    }
+
+    Ok(None)
+}
+
+/// Simulate building every branch in `branch_map` as git-disjoint would.
+///
+/// A branch that already exists, and holds no outdated version of a planned
+/// commit, only gets the planned commits it does not hold yet, picked onto its
+/// head. Every other branch is built from `base_commit`.
 pub fn validate<'repo>(
     branch_map: &DisjointBranchMap<'repo>,
     base_commit: &Commit<'repo>,
-    repo: &git2::Repository,
+    repo: &'repo Repository,
 ) -> Result<(), PreValidationReport> {
     let mut conflicts = Vec::new();
 
     for (_issue_group, branch) in branch_map.iter() {
-        let mut simulated_head = base_commit.clone();
-
-        for commit in &branch.commits {
-            let mut index = repo
-                .cherrypick_commit(commit, &simulated_head, 0, None)
-                .map_err(|_| PreValidationReport {
-                    conflicts: vec![BranchConflict {
-                        branch_name: branch.branch_name.clone(),
-                        commit_summary: commit.summary().unwrap_or("").to_string(),
-                        conflicting_paths: vec!["(git2 error)".to_string()],
-                    }],
-                })?;
-
-            if index.has_conflicts() {
-                let conflicting_paths: Vec<String> = index
-                    .conflicts()
-                    .ok()
-                    .into_iter()
-                    .flatten()
-                    .filter_map(|conflict| {
-                        let conflict = conflict.ok()?;
-                        conflict
-                            .our
-                            .or(conflict.their)
-                            .or(conflict.ancestor)
-                            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
-                    })
-                    .collect();
-
-                conflicts.push(BranchConflict {
-                    branch_name: branch.branch_name.clone(),
-                    commit_summary: commit.summary().unwrap_or("").to_string(),
-                    conflicting_paths,
-                });
-                // Stop simulating this branch after first conflict
-                break;
-            } else {
-                // Advance simulated head
-                let tree_oid = index.write_tree_to(repo).map_err(|_| PreValidationReport {
-                    conflicts: vec![BranchConflict {
-                        branch_name: branch.branch_name.clone(),
-                        commit_summary: commit.summary().unwrap_or("").to_string(),
-                        conflicting_paths: vec!["(write_tree error)".to_string()],
-                    }],
-                })?;
-                let tree = repo.find_tree(tree_oid).map_err(|_| PreValidationReport {
-                    conflicts: vec![BranchConflict {
-                        branch_name: branch.branch_name.clone(),
-                        commit_summary: commit.summary().unwrap_or("").to_string(),
-                        conflicting_paths: vec!["(find_tree error)".to_string()],
-                    }],
-                })?;
-                let sig = commit.author();
-                simulated_head = repo
-                    .find_commit(
-                        repo.commit(None, &sig, &sig, "simulated", &tree, &[&simulated_head])
-                            .map_err(|_| PreValidationReport {
-                                conflicts: vec![BranchConflict {
-                                    branch_name: branch.branch_name.clone(),
-                                    commit_summary: commit.summary().unwrap_or("").to_string(),
-                                    conflicting_paths: vec!["(commit error)".to_string()],
-                                }],
-                            })?,
-                    )
-                    .map_err(|_| PreValidationReport {
-                        conflicts: vec![BranchConflict {
-                            branch_name: branch.branch_name.clone(),
-                            commit_summary: commit.summary().unwrap_or("").to_string(),
-                            conflicting_paths: vec!["(find_commit error)".to_string()],
-                        }],
-                    })?;
+        let error = |commit: &Commit, step: &str| PreValidationReport {
+            conflicts: vec![BranchConflict {
+                branch_name: branch.branch_name.clone(),
+                commit_summary: commit.summary().unwrap_or("").to_string(),
+                conflicting_paths: vec![step.to_string()],
+            }],
+        };
+        let existing_head = repo
+            .revparse_single(&format!("refs/heads/{}", branch.branch_name))
+            .and_then(|object| object.peel_to_commit())
+            .ok();
+        let missing_commits = match &existing_head {
+            Some(head) => {
+                let outdated = repo
+                    .outdated_commits(head, base_commit, &branch.commits)
+                    .map_err(|_| error(head, "(walk error)"))?;
+                match outdated.is_empty() {
+                    true => Some(
+                        repo.missing_commits(head, base_commit, &branch.commits)
+                            .map_err(|_| error(head, "(walk error)"))?,
+                    ),
+                    false => None,
+                }
             }
+            None => None,
+        };
+
+        // Fixup commits are picked right after their target, which leaves
+        // the same tree as squashing them. A stacked branch is built on the
+        // branches beneath it.
+        let simulation = match (&existing_head, &missing_commits) {
+            (Some(head), Some(missing)) => simulate(
+                branch
+                    .commits
+                    .iter()
+                    .filter(|commit| missing.contains(&commit.commit.id()))
+                    .flat_map(SquashedCommit::commits),
+                head,
+                repo,
+            ),
+            _ => simulate(branch_map.stack_commits(branch), base_commit, repo),
+        };
+        match simulation {
+            Ok(None) => {}
+            // Stop simulating this branch after first conflict
+            Ok(Some(conflict)) => conflicts.push(BranchConflict {
+                branch_name: branch.branch_name.clone(),
+                commit_summary: conflict.commit.summary().unwrap_or("").to_string(),
+                conflicting_paths: conflict.conflicting_paths,
+            }),
+            Err((commit, step)) => return Err(error(&commit, step)),
         }
     }
 
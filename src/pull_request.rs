@@ -15,6 +15,8 @@ pub struct PullRequest {
     pub branch_name: BranchName,
     pub base: DefaultBranch,
     pub draft: bool,
+    pub labels: Vec<String>,
+    pub milestone: Option<u64>,
 }
 
 // https://docs.github.com/en/rest/pulls/pulls?apiVersion=2022-11-28#create-a-pull-request
@@ -30,9 +32,21 @@ struct CreatePullRequestRequest {
 // https://docs.github.com/en/rest/pulls/pulls?apiVersion=2022-11-28#create-a-pull-request
 #[derive(Debug, Deserialize)]
 struct CreatePullRequestResponse {
+    number: u64,
     html_url: String,
 }
 
+// Labels and milestones are set through the issues API, since every pull
+// request is also an issue.
+// https://docs.github.com/en/rest/issues/issues?apiVersion=2022-11-28#update-an-issue
+#[derive(Debug, Serialize)]
+struct UpdateIssueRequest {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<u64>,
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct CreatePullRequestError {
@@ -47,6 +61,9 @@ impl Display for CreatePullRequestError {
             CreatePullRequestErrorKind::Parse(_) => {
                 write!(f, "unable to parse response from POST {}", self.url)
             }
+            CreatePullRequestErrorKind::UpdateIssue(_) => {
+                write!(f, "unable to set labels and milestone: PATCH {}", self.url)
+            }
             CreatePullRequestErrorKind::OpenBrowser(_) => {
                 write!(f, "unable to open web browser to page {}", self.url)
             }
@@ -59,6 +76,7 @@ impl Error for CreatePullRequestError {
         match &self.kind {
             CreatePullRequestErrorKind::Http(err) => Some(err),
             CreatePullRequestErrorKind::Parse(err) => Some(err),
+            CreatePullRequestErrorKind::UpdateIssue(err) => Some(err),
             CreatePullRequestErrorKind::OpenBrowser(err) => Some(err),
         }
     }
@@ -71,6 +89,8 @@ pub enum CreatePullRequestErrorKind {
     #[non_exhaustive]
     Parse(reqwest::Error),
     #[non_exhaustive]
+    UpdateIssue(reqwest::Error),
+    #[non_exhaustive]
     OpenBrowser(io::Error),
 }
 
@@ -85,10 +105,21 @@ impl PullRequest {
         }
     }
 
+    fn build_update_request(&self) -> Option<UpdateIssueRequest> {
+        if self.labels.is_empty() && self.milestone.is_none() {
+            return None;
+        }
+        Some(UpdateIssueRequest {
+            labels: self.labels.clone(),
+            milestone: self.milestone,
+        })
+    }
+
+    /// Create the pull request, returning its number.
     pub fn create(
         self,
         http_client: reqwest::blocking::Client,
-    ) -> Result<(), CreatePullRequestError> {
+    ) -> Result<u64, CreatePullRequestError> {
         let url = format!(
             "https://api.github.com/repos/{}/{}/pulls",
             self.owner, self.name
@@ -110,13 +141,32 @@ impl PullRequest {
                 kind: CreatePullRequestErrorKind::Parse(err),
             })?;
 
+        if let Some(update_request) = self.build_update_request() {
+            let url = format!(
+                "https://api.github.com/repos/{}/{}/issues/{}",
+                self.owner, self.name, response.number
+            );
+            http_client
+                .patch(&url)
+                .header("User-Agent", "git-disjoint")
+                .header("Accept", "application/vnd.github.v3+json")
+                .header("Authorization", format!("token {}", self.github_token))
+                .json(&update_request)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .map_err(|err| CreatePullRequestError {
+                    url,
+                    kind: CreatePullRequestErrorKind::UpdateIssue(err),
+                })?;
+        }
+
         let url = response.html_url;
         open::that(&url).map_err(|err| CreatePullRequestError {
             url,
             kind: CreatePullRequestErrorKind::OpenBrowser(err),
         })?;
 
-        Ok(())
+        Ok(response.number)
     }
 }
 
@@ -126,6 +176,14 @@ mod test {
     use crate::{branch_name::BranchName, default_branch::DefaultBranch};
 
     fn test_pull_request(draft: bool) -> PullRequest {
+        test_pull_request_with_issue_metadata(draft, Vec::new(), None)
+    }
+
+    fn test_pull_request_with_issue_metadata(
+        draft: bool,
+        labels: Vec<String>,
+        milestone: Option<u64>,
+    ) -> PullRequest {
         PullRequest {
             owner: "owner".into(),
             name: "repo".into(),
@@ -136,6 +194,8 @@ mod test {
             branch_name: BranchName::new("proj-123-fix-the-widget".into()),
             base: DefaultBranch("main".into()),
             draft,
+            labels,
+            milestone,
         }
     }
 
@@ -152,4 +212,21 @@ mod test {
         let req = pr.build_request();
         insta::assert_snapshot!(serde_json::to_string_pretty(&req).unwrap());
     }
+
+    #[test]
+    fn build_update_request_carries_labels_and_milestone() {
+        let pr = test_pull_request_with_issue_metadata(
+            true,
+            vec!["bug".to_string(), "parser".to_string()],
+            Some(3),
+        );
+        let req = pr.build_update_request().unwrap();
+        insta::assert_snapshot!(serde_json::to_string_pretty(&req).unwrap());
+    }
+
+    #[test]
+    fn build_update_request_without_issue_metadata() {
+        let pr = test_pull_request(true);
+        assert!(pr.build_update_request().is_none());
+    }
 }
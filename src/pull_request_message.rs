@@ -16,19 +16,31 @@ Changes:
 
 #[derive(Clone, Debug)]
 pub struct PullRequestMessageTemplate<'repo> {
+    title: Option<String>,
     commits: Vec<&'repo Commit<'repo>>,
 }
 
 impl<'repo> FromIterator<&'repo Commit<'repo>> for PullRequestMessageTemplate<'repo> {
     fn from_iter<T: IntoIterator<Item = &'repo Commit<'repo>>>(iter: T) -> Self {
         Self {
+            title: None,
             commits: iter.into_iter().collect(),
         }
     }
 }
 
+impl PullRequestMessageTemplate<'_> {
+    /// Offer `title` as the pull request's title.
+    pub fn with_title(self, title: Option<String>) -> Self {
+        Self { title, ..self }
+    }
+}
+
 impl Display for PullRequestMessageTemplate<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "{title}")?;
+        }
         write!(f, "\n{}\n{}", IGNORE_MARKER, PULL_REQUEST_INSTRUCTIONS)?;
         for commit in self.commits.iter().rev() {
             writeln!(
@@ -1,6 +1,9 @@
 use std::{error::Error, fmt::Display, str::FromStr};
 
-use crate::pull_request_message::IGNORE_MARKER;
+use crate::{
+    branch_name::BranchName, fill, issue::IssueReference, pull_request_message::IGNORE_MARKER,
+    tracker_url::TrackerUrls,
+};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct PullRequestMetadata {
@@ -44,6 +47,98 @@ impl From<FromStrErrorKind> for FromStrError {
     }
 }
 
+impl PullRequestMetadata {
+    /// The metadata of a single-commit PR: the commit's subject as the title,
+    /// and its body, with hard-wrapped lines filled, as the body.
+    ///
+    /// The commit's subject is kept even when the commit closes an issue;
+    /// `issue_title` is used only when the subject is empty.
+    pub fn from_commit_message(
+        message: &str,
+        issue_title: Option<String>,
+    ) -> Result<Self, FromStrError> {
+        let mut metadata: Self = message.parse()?;
+        metadata.body = fill::fill_lines(&metadata.body);
+        if let (true, Some(issue_title)) = (metadata.title.is_empty(), issue_title) {
+            metadata.title = issue_title;
+        }
+        Ok(metadata)
+    }
+
+    /// Prefix the title with an issue key, e.g. `ABC-3: Add login page`,
+    /// unless the title already mentions it.
+    pub fn prefix_title(&mut self, issue_key: &str) {
+        if !self.title.contains(issue_key) {
+            self.title = format!("{issue_key}: {}", self.title);
+        }
+    }
+
+    /// Append the stack this PR is part of to the body, bottom first, marking
+    /// where `branch_name` is in it.
+    pub fn append_stack(&mut self, stack: &[BranchName], branch_name: &BranchName) {
+        let Some(position) = stack.iter().position(|branch| branch == branch_name) else {
+            return;
+        };
+        if stack.len() < 2 {
+            return;
+        }
+        let mut section = format!(
+            "This PR is part of a stack ({} of {}):\n",
+            position + 1,
+            stack.len()
+        );
+        for (i, branch) in stack.iter().enumerate() {
+            let marker = match i == position {
+                true => " (this PR)",
+                false => "",
+            };
+            section.push_str(&format!("\n{}. `{branch}`{marker}", i + 1));
+        }
+        self.body = match self.body.is_empty() {
+            true => section,
+            false => format!("{}\n\n{section}", self.body),
+        };
+    }
+
+    /// Append a line to the body for each PR this PR depends on, e.g.
+    /// `Depends on #12`.
+    pub fn append_dependencies(&mut self, dependencies: &[String]) {
+        if dependencies.is_empty() {
+            return;
+        }
+        let section = dependencies
+            .iter()
+            .map(|dependency| format!("Depends on {dependency}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.body = match self.body.is_empty() {
+            true => section,
+            false => format!("{}\n\n{section}", self.body),
+        };
+    }
+
+    /// Append a footer to the body with one normalized line per issue in
+    /// `references`, e.g. `Closes #12` or `Refs ABC-3`. Issues of projects
+    /// with a tracker URL are linked.
+    ///
+    /// Lines the body already contains are not repeated.
+    pub fn append_footer(&mut self, references: &[IssueReference], tracker_urls: &TrackerUrls) {
+        let footer = references
+            .iter()
+            .map(|reference| tracker_urls.render_reference(reference))
+            .filter(|line| !self.body.lines().any(|existing| existing.trim() == line))
+            .collect::<Vec<_>>();
+        if footer.is_empty() {
+            return;
+        }
+        let footer = footer.join("\n");
+        self.body = match self.body.is_empty() {
+            true => footer,
+            false => format!("{}\n\n{footer}", self.body),
+        };
+    }
+}
+
 impl FromStr for PullRequestMetadata {
     type Err = FromStrError;
 
@@ -68,7 +163,33 @@ impl FromStr for PullRequestMetadata {
 #[cfg(test)]
 mod test {
     use super::PullRequestMetadata;
-    use crate::pull_request_message::IGNORE_MARKER;
+    use crate::{
+        branch_name::BranchName,
+        issue::{Issue, IssueIntent, IssueReference},
+        pull_request_message::IGNORE_MARKER,
+        tracker_url::{TrackerUrl, TrackerUrls},
+    };
+
+    #[test]
+    fn single_commit_keeps_its_subject_over_the_issue_title() {
+        let metadata = PullRequestMetadata::from_commit_message(
+            "fix: handle empty input\n\nCloses #42",
+            Some("Parser crashes on empty input".to_string()),
+        )
+        .unwrap();
+        assert_eq!(metadata.title, "fix: handle empty input");
+        assert_eq!(metadata.body, "Closes #42");
+    }
+
+    #[test]
+    fn single_commit_without_subject_takes_the_issue_title() {
+        let metadata = PullRequestMetadata::from_commit_message(
+            "\nCloses #42",
+            Some("Parser crashes on empty input".to_string()),
+        )
+        .unwrap();
+        assert_eq!(metadata.title, "Parser crashes on empty input");
+    }
 
     #[test]
     fn parse_empty_string_returns_error() {
@@ -108,4 +229,146 @@ mod test {
         assert_eq!(result.title, "Title with spaces");
         assert_eq!(result.body, "Body with spaces");
     }
+
+    fn closes(issue: Issue) -> IssueReference {
+        IssueReference {
+            issue,
+            intent: IssueIntent::Closes,
+        }
+    }
+
+    fn refs(issue: Issue) -> IssueReference {
+        IssueReference {
+            issue,
+            intent: IssueIntent::References,
+        }
+    }
+
+    #[test]
+    fn append_footer_is_fully_qualified() {
+        let mut metadata = "Title\n\nFixes https://github.com/org/repo/issues/3"
+            .parse::<PullRequestMetadata>()
+            .unwrap();
+        metadata.append_footer(
+            &[closes(Issue::GitHubCrossRepository {
+                owner: "org".to_string(),
+                repository: "repo".to_string(),
+                number: "3".to_string(),
+            })],
+            &TrackerUrls::default(),
+        );
+        assert_eq!(
+            metadata.body,
+            "Fixes https://github.com/org/repo/issues/3\n\nCloses org/repo#3"
+        );
+    }
+
+    #[test]
+    fn append_footer_does_not_repeat_lines() {
+        let mut metadata = "Title\n\nCloses org/repo#3"
+            .parse::<PullRequestMetadata>()
+            .unwrap();
+        metadata.append_footer(
+            &[closes(Issue::GitHubCrossRepository {
+                owner: "org".to_string(),
+                repository: "repo".to_string(),
+                number: "3".to_string(),
+            })],
+            &TrackerUrls::default(),
+        );
+        assert_eq!(metadata.body, "Closes org/repo#3");
+    }
+
+    #[test]
+    fn append_footer_after_body() {
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_footer(
+            &[
+                closes(Issue::GitHub("12".to_string())),
+                refs(Issue::WorkTracker("ABC-3".to_string())),
+            ],
+            &TrackerUrls::default(),
+        );
+        assert_eq!(metadata.body, "Body\n\nCloses #12\nRefs ABC-3");
+    }
+
+    #[test]
+    fn append_footer_to_empty_body() {
+        let mut metadata = "Title".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_footer(
+            &[refs(Issue::GitHub("42".to_string()))],
+            &TrackerUrls::default(),
+        );
+        assert_eq!(metadata.body, "Refs #42");
+    }
+
+    #[test]
+    fn append_empty_footer_leaves_body_unchanged() {
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_footer(&[], &TrackerUrls::default());
+        assert_eq!(metadata.body, "Body");
+    }
+
+    #[test]
+    fn append_footer_links_tracker_issues() {
+        let tracker_urls: TrackerUrls = ["ABC:https://acme.atlassian.net/browse/{id}"
+            .parse::<TrackerUrl>()
+            .unwrap()]
+        .into_iter()
+        .collect();
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_footer(
+            &[
+                closes(Issue::WorkTracker("ABC-3".to_string())),
+                refs(Issue::WorkTracker("OPS-1".to_string())),
+            ],
+            &tracker_urls,
+        );
+        assert_eq!(
+            metadata.body,
+            "Body\n\nCloses [ABC-3](https://acme.atlassian.net/browse/ABC-3)\nRefs OPS-1"
+        );
+    }
+
+    #[test]
+    fn prefix_title_with_issue_key() {
+        let mut metadata = "Add login page".parse::<PullRequestMetadata>().unwrap();
+        metadata.prefix_title("ABC-3");
+        assert_eq!(metadata.title, "ABC-3: Add login page");
+        metadata.prefix_title("ABC-3");
+        assert_eq!(metadata.title, "ABC-3: Add login page");
+    }
+
+    #[test]
+    fn append_stack_marks_position() {
+        let stack = [
+            BranchName::new("ab-1-add-config".to_string()),
+            BranchName::new("ab-2-extend-config".to_string()),
+            BranchName::new("ab-3-use-config".to_string()),
+        ];
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_stack(&stack, &stack[1]);
+        assert_eq!(
+            metadata.body,
+            "Body\n\nThis PR is part of a stack (2 of 3):\n\n\
+             1. `ab-1-add-config`\n\
+             2. `ab-2-extend-config` (this PR)\n\
+             3. `ab-3-use-config`"
+        );
+    }
+
+    #[test]
+    fn append_stack_without_stack_leaves_body_unchanged() {
+        let branch = BranchName::new("ab-1-add-config".to_string());
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_stack(std::slice::from_ref(&branch), &branch);
+        assert_eq!(metadata.body, "Body");
+    }
+
+    #[test]
+    fn append_dependencies_after_body() {
+        let mut metadata = "Title\n\nBody".parse::<PullRequestMetadata>().unwrap();
+        metadata.append_dependencies(&["#12".to_string(), "#15".to_string()]);
+        assert_eq!(metadata.body, "Body\n\nDepends on #12\nDepends on #15");
+    }
 }
@@ -0,0 +1,698 @@
+//! Regroup commits in a full-screen editor, the way `--regroup` does.
+//!
+//! Each issue group is a column of commits. Commits can be moved between
+//! groups and reordered, groups can be split, and branches renamed. Every
+//! group is cherry-picked onto the base in memory after each change, and
+//! groups that conflict are flagged before the regrouping is confirmed.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use git2::Commit;
+
+use crate::{
+    autosquash::SquashedCommit,
+    branch_name::BranchName,
+    disjoint_branch::{DisjointBranch, DisjointBranchMap},
+    issue_group::{GroupName, IssueGroup},
+    pre_validation,
+};
+
+const KEY_HELP: &str = "←→↑↓ select · shift+←→ move commit · shift+↑↓ reorder · \
+                        s split · r rename · enter confirm · q abort";
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RegroupError {
+    kind: RegroupErrorKind,
+}
+
+impl Display for RegroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            RegroupErrorKind::Terminal(_) => write!(f, "unable to draw the regrouping editor"),
+            RegroupErrorKind::Aborted => write!(f, "user aborted regrouping"),
+        }
+    }
+}
+
+impl Error for RegroupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RegroupErrorKind::Terminal(err) => Some(err),
+            RegroupErrorKind::Aborted => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RegroupErrorKind {
+    #[non_exhaustive]
+    Terminal(io::Error),
+    #[non_exhaustive]
+    Aborted,
+}
+
+impl From<io::Error> for RegroupError {
+    fn from(err: io::Error) -> Self {
+        Self {
+            kind: RegroupErrorKind::Terminal(err),
+        }
+    }
+}
+
+/// The groups being edited, and the commit selected in them.
+pub struct Regrouping<'repo> {
+    groups: Vec<(IssueGroup, DisjointBranch<'repo>)>,
+    /// The subject of the first commit in each group that does not
+    /// cherry-pick cleanly onto the base, if any.
+    conflicts: Vec<Option<String>>,
+    column: usize,
+    row: usize,
+    base_commit: Commit<'repo>,
+    repo: &'repo git2::Repository,
+}
+
+impl<'repo> Regrouping<'repo> {
+    pub fn new(
+        branch_map: DisjointBranchMap<'repo>,
+        base_commit: &Commit<'repo>,
+        repo: &'repo git2::Repository,
+    ) -> Self {
+        let mut regrouping = Self {
+            groups: branch_map.into_iter().collect(),
+            conflicts: Vec::new(),
+            column: 0,
+            row: 0,
+            base_commit: base_commit.clone(),
+            repo,
+        };
+        regrouping.validate();
+        regrouping
+    }
+
+    /// Cherry-pick every group onto the base in memory, and record the
+    /// groups that conflict.
+    fn validate(&mut self) {
+        self.conflicts = self
+            .groups
+            .iter()
+            .map(|(_issue_group, branch)| {
+                let commits = branch.commits.iter().flat_map(SquashedCommit::commits);
+                match pre_validation::simulate(commits, &self.base_commit, self.repo) {
+                    Ok(None) => None,
+                    Ok(Some(conflict)) => Some(conflict.commit.summary().unwrap_or("").to_string()),
+                    Err((commit, _step)) => Some(commit.summary().unwrap_or("").to_string()),
+                }
+            })
+            .collect();
+    }
+
+    /// The number of groups that do not cherry-pick cleanly.
+    pub fn conflict_count(&self) -> usize {
+        self.conflicts.iter().flatten().count()
+    }
+
+    fn commit_count(&self, column: usize) -> usize {
+        self.groups
+            .get(column)
+            .map_or(0, |(_issue_group, branch)| branch.commits.len())
+    }
+
+    /// Keep the selected commit within the selected group.
+    fn clamp_row(&mut self) {
+        self.row = self
+            .row
+            .min(self.commit_count(self.column).saturating_sub(1));
+    }
+
+    pub fn select_column(&mut self, offset: isize) {
+        self.column = self
+            .column
+            .saturating_add_signed(offset)
+            .min(self.groups.len().saturating_sub(1));
+        self.clamp_row();
+    }
+
+    pub fn select_row(&mut self, offset: isize) {
+        self.row = self.row.saturating_add_signed(offset);
+        self.clamp_row();
+    }
+
+    /// Move the selected commit to the end of the neighbouring group.
+    pub fn move_commit(&mut self, offset: isize) {
+        let Some(target) = self
+            .column
+            .checked_add_signed(offset)
+            .filter(|target| *target < self.groups.len())
+        else {
+            return;
+        };
+        if self.row >= self.commit_count(self.column) {
+            return;
+        }
+        let commit = self.groups[self.column].1.commits.remove(self.row);
+        self.groups[target].1.commits.push(commit);
+        self.column = target;
+        self.row = self.commit_count(target) - 1;
+        self.validate();
+    }
+
+    /// Swap the selected commit with its neighbour in the same group.
+    pub fn reorder_commit(&mut self, offset: isize) {
+        let commits = &mut self.groups[self.column].1.commits;
+        let Some(target) = self
+            .row
+            .checked_add_signed(offset)
+            .filter(|target| *target < commits.len())
+        else {
+            return;
+        };
+        commits.swap(self.row, target);
+        self.row = target;
+        self.validate();
+    }
+
+    /// Move the selected commit, and every commit after it, into a new group
+    /// right of the selected group.
+    pub fn split(&mut self) {
+        if self.row == 0 || self.row >= self.commit_count(self.column) {
+            return;
+        }
+        let (_issue_group, branch) = &mut self.groups[self.column];
+        let commits = branch.commits.split_off(self.row);
+        let branch_name = self.unused_branch_name(&self.groups[self.column].1.branch_name);
+        let issue_group = IssueGroup::Named(GroupName(branch_name.to_string()));
+        self.groups.insert(
+            self.column + 1,
+            (
+                issue_group,
+                DisjointBranch {
+                    branch_name,
+                    commits,
+                    stacked_on: None,
+                    depends_on: Vec::new(),
+                },
+            ),
+        );
+        self.column += 1;
+        self.row = 0;
+        self.validate();
+    }
+
+    /// The first of `<branch_name>-2`, `<branch_name>-3`, ... that no group
+    /// uses, as a branch name or as the name of its group. A renamed group
+    /// keeps its group name, so both must be checked.
+    fn unused_branch_name(&self, branch_name: &BranchName) -> BranchName {
+        (2..)
+            .map(|n| BranchName::new(format!("{branch_name}-{n}")))
+            .find(|candidate| {
+                let issue_group = IssueGroup::Named(GroupName(candidate.to_string()));
+                !self.uses_branch_name(candidate)
+                    && self
+                        .groups
+                        .iter()
+                        .all(|(existing, _branch)| existing != &issue_group)
+            })
+            .expect("some suffix should be unused")
+    }
+
+    fn uses_branch_name(&self, branch_name: &BranchName) -> bool {
+        self.groups
+            .iter()
+            .any(|(_issue_group, branch)| &branch.branch_name == branch_name)
+    }
+
+    /// Rename the selected group's branch, unless the name is empty, is not a
+    /// valid git branch name, or is used by another group.
+    pub fn rename(&mut self, name: &str) -> Result<(), &'static str> {
+        let branch_name = BranchName::new(name.trim().to_string());
+        if branch_name.as_str().is_empty() {
+            return Err("branch name cannot be empty");
+        }
+        if !git2::Branch::name_is_valid(branch_name.as_str()).unwrap_or(false) {
+            return Err("that is not a valid git branch name");
+        }
+        if branch_name != self.groups[self.column].1.branch_name
+            && self.uses_branch_name(&branch_name)
+        {
+            return Err("another group already uses that branch name");
+        }
+        self.groups[self.column].1.branch_name = branch_name;
+        Ok(())
+    }
+
+    /// The regrouped branches, without groups left empty.
+    pub fn into_branch_map(self) -> DisjointBranchMap<'repo> {
+        self.groups
+            .into_iter()
+            .filter(|(_issue_group, branch)| !branch.commits.is_empty())
+            .collect()
+    }
+
+    /// Draw each group as a bordered column of commits, with `status` and
+    /// the key help below them.
+    fn render(&self, out: &mut impl Write, status: &str) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (usize::from(width), usize::from(height));
+        queue!(out, terminal::Clear(ClearType::All))?;
+
+        let column_width = width / self.groups.len().max(1);
+        // Leave room for the status and key help lines
+        let column_height = height.saturating_sub(2);
+        if column_width >= 2 && column_height >= 2 {
+            let inner_width = column_width - 2;
+            let visible_rows = column_height - 2;
+            for (column, ((issue_group, branch), conflict)) in
+                self.groups.iter().zip(&self.conflicts).enumerate()
+            {
+                let selected = column == self.column;
+                let x = u16::try_from(column * column_width).unwrap_or(u16::MAX);
+                let border = match conflict {
+                    Some(_) => Color::Red,
+                    None if selected => Color::Yellow,
+                    None => Color::Reset,
+                };
+                let bottom_right = conflict
+                    .as_ref()
+                    .map(|summary| format!("conflict at {summary:?}"))
+                    .unwrap_or_default();
+
+                queue!(out, SetForegroundColor(border))?;
+                if selected {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+                queue!(
+                    out,
+                    cursor::MoveTo(x, 0),
+                    Print(format!(
+                        "┌{}┐",
+                        border_line(&branch.branch_name.to_string(), "", inner_width)
+                    )),
+                    cursor::MoveTo(x, u16::try_from(column_height - 1).unwrap_or(u16::MAX)),
+                    Print(format!(
+                        "└{}┘",
+                        border_line(&issue_group.to_string(), &bottom_right, inner_width)
+                    )),
+                )?;
+                for row in 1..column_height - 1 {
+                    let y = u16::try_from(row).unwrap_or(u16::MAX);
+                    queue!(
+                        out,
+                        cursor::MoveTo(x, y),
+                        Print("│"),
+                        cursor::MoveTo(x + 1 + u16::try_from(inner_width).unwrap_or(0), y),
+                        Print("│"),
+                    )?;
+                }
+                queue!(out, SetAttribute(Attribute::Reset), ResetColor)?;
+
+                // Scroll to keep the selected commit in view
+                let offset = match selected {
+                    true => (self.row + 1).saturating_sub(visible_rows),
+                    false => 0,
+                };
+                for (index, commit) in branch
+                    .commits
+                    .iter()
+                    .enumerate()
+                    .skip(offset)
+                    .take(visible_rows)
+                {
+                    let line = format!(
+                        "{:.7} {}",
+                        commit.commit.id(),
+                        commit.commit.summary().unwrap_or("")
+                    );
+                    let y = u16::try_from(1 + index - offset).unwrap_or(u16::MAX);
+                    queue!(out, cursor::MoveTo(x + 1, y))?;
+                    if selected && index == self.row {
+                        queue!(out, SetAttribute(Attribute::Reverse))?;
+                    }
+                    queue!(
+                        out,
+                        Print(fit(&line, inner_width)),
+                        SetAttribute(Attribute::Reset)
+                    )?;
+                }
+            }
+        }
+
+        queue!(
+            out,
+            cursor::MoveTo(
+                0,
+                u16::try_from(height.saturating_sub(2)).unwrap_or(u16::MAX)
+            ),
+            Print(fit(status, width)),
+            cursor::MoveTo(
+                0,
+                u16::try_from(height.saturating_sub(1)).unwrap_or(u16::MAX)
+            ),
+            SetAttribute(Attribute::Dim),
+            Print(fit(KEY_HELP, width)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        out.flush()
+    }
+}
+
+/// `text`, cut or padded with spaces to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    format!("{:width$}", text.chars().take(width).collect::<String>())
+}
+
+/// A horizontal border `width` characters long, with `left` at its start and
+/// `right` at its end, as far as they fit.
+fn border_line(left: &str, right: &str, width: usize) -> String {
+    let left: String = left.chars().take(width).collect();
+    let room = width - left.chars().count();
+    let right: String = right.chars().take(room).collect();
+    let fill = room - right.chars().count();
+    format!("{left}{}{right}", "─".repeat(fill))
+}
+
+/// What typed keys are doing.
+enum Mode {
+    Navigate,
+    /// Typing a new name for the selected group's branch.
+    Rename(String),
+}
+
+fn status(regrouping: &Regrouping, mode: &Mode, message: Option<&str>) -> String {
+    match (mode, message) {
+        (Mode::Rename(name), _) => format!("rename branch: {name}█"),
+        (Mode::Navigate, Some(message)) => message.to_string(),
+        (Mode::Navigate, None) => match regrouping.conflict_count() {
+            0 => "every group cherry-picks cleanly".to_string(),
+            1 => "1 group conflicts".to_string(),
+            n => format!("{n} groups conflict"),
+        },
+    }
+}
+
+fn run<'repo>(
+    out: &mut impl Write,
+    mut regrouping: Regrouping<'repo>,
+) -> Result<DisjointBranchMap<'repo>, RegroupError> {
+    let mut mode = Mode::Navigate;
+    let mut message: Option<&'static str> = None;
+    loop {
+        let status = status(&regrouping, &mode, message);
+        regrouping.render(out, &status)?;
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        message = None;
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        match &mut mode {
+            Mode::Rename(name) => match code {
+                KeyCode::Enter => {
+                    message = regrouping.rename(name).err();
+                    mode = Mode::Navigate;
+                }
+                KeyCode::Esc => mode = Mode::Navigate,
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Char(c) => name.push(c),
+                _ => {}
+            },
+            Mode::Navigate => match code {
+                KeyCode::Left if shift => regrouping.move_commit(-1),
+                KeyCode::Right if shift => regrouping.move_commit(1),
+                KeyCode::Up if shift => regrouping.reorder_commit(-1),
+                KeyCode::Down if shift => regrouping.reorder_commit(1),
+                KeyCode::Char('H') => regrouping.move_commit(-1),
+                KeyCode::Char('L') => regrouping.move_commit(1),
+                KeyCode::Char('K') => regrouping.reorder_commit(-1),
+                KeyCode::Char('J') => regrouping.reorder_commit(1),
+                KeyCode::Left | KeyCode::Char('h') => regrouping.select_column(-1),
+                KeyCode::Right | KeyCode::Char('l') => regrouping.select_column(1),
+                KeyCode::Up | KeyCode::Char('k') => regrouping.select_row(-1),
+                KeyCode::Down | KeyCode::Char('j') => regrouping.select_row(1),
+                KeyCode::Char('s') => regrouping.split(),
+                KeyCode::Char('r') => {
+                    let (_issue_group, branch) = &regrouping.groups[regrouping.column];
+                    mode = Mode::Rename(branch.branch_name.to_string());
+                }
+                KeyCode::Enter => return Ok(regrouping.into_branch_map()),
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    return Err(RegroupError {
+                        kind: RegroupErrorKind::Aborted,
+                    })
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Let the user regroup `branch_map` in a full-screen editor.
+pub fn edit<'repo>(
+    branch_map: DisjointBranchMap<'repo>,
+    base_commit: &Commit<'repo>,
+    repo: &'repo git2::Repository,
+) -> Result<DisjointBranchMap<'repo>, RegroupError> {
+    if branch_map.is_empty() {
+        return Ok(branch_map);
+    }
+    let regrouping = Regrouping::new(branch_map, base_commit, repo);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    crossterm::execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    let result = run(&mut stdout, regrouping);
+    // Restore the terminal even when the editor failed
+    crossterm::execute!(stdout, cursor::Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::{border_line, fit, Regrouping};
+    use crate::{
+        autosquash,
+        branch_name::BranchName,
+        disjoint_branch::{DisjointBranch, DisjointBranchMap},
+        issue_group::{GroupName, IssueGroup},
+    };
+
+    /// Commit `contents` to `path` on top of `parent`.
+    fn commit(
+        repo: &git2::Repository,
+        parent: Option<git2::Oid>,
+        message: &str,
+        path: &str,
+        contents: &str,
+    ) -> git2::Oid {
+        let signature = git2::Signature::now("Test User", "test@test.com").unwrap();
+        let mut index = repo.index().unwrap();
+        std::fs::write(repo.workdir().unwrap().join(path), contents).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents: Vec<_> = parent
+            .map(|parent| repo.find_commit(parent).unwrap())
+            .into_iter()
+            .collect();
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(None, &signature, &signature, message, &tree, &parents)
+            .unwrap()
+    }
+
+    /// A base commit, then "add a" and "add b" on separate files, then
+    /// "change a", which depends on "add a".
+    fn repository() -> (TempDir, git2::Repository, [git2::Oid; 4]) {
+        let tempdir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(tempdir.path()).unwrap();
+        let base = commit(&repo, None, "base", "README", "readme\n");
+        let add_a = commit(&repo, Some(base), "add a", "a", "a\n");
+        let add_b = commit(&repo, Some(add_a), "add b", "b", "b\n");
+        let change_a = commit(&repo, Some(add_b), "change a", "a", "a changed\n");
+        (tempdir, repo, [base, add_a, add_b, change_a])
+    }
+
+    fn branch<'repo>(
+        repo: &'repo git2::Repository,
+        name: &str,
+        commits: &[git2::Oid],
+    ) -> (IssueGroup, DisjointBranch<'repo>) {
+        (
+            IssueGroup::Named(GroupName(name.to_string())),
+            DisjointBranch {
+                branch_name: BranchName::new(name.to_string()),
+                commits: autosquash::fold(
+                    commits.iter().map(|oid| repo.find_commit(*oid).unwrap()),
+                ),
+                stacked_on: None,
+                depends_on: Vec::new(),
+            },
+        )
+    }
+
+    fn summaries(branch_map: &DisjointBranchMap) -> Vec<(String, Vec<String>)> {
+        branch_map
+            .iter()
+            .map(|(_issue_group, branch)| {
+                (
+                    branch.branch_name.to_string(),
+                    branch
+                        .commits
+                        .iter()
+                        .map(|commit| commit.commit.summary().unwrap().to_string())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn moving_a_dependent_commit_resolves_the_conflict() {
+        let (_tempdir, repo, [base, add_a, add_b, change_a]) = repository();
+        let branch_map: DisjointBranchMap = [
+            branch(&repo, "a", &[add_a]),
+            branch(&repo, "b", &[add_b, change_a]),
+        ]
+        .into_iter()
+        .collect();
+        let base = repo.find_commit(base).unwrap();
+        let mut regrouping = Regrouping::new(branch_map, &base, &repo);
+        assert_eq!(regrouping.conflict_count(), 1);
+
+        regrouping.select_column(1);
+        regrouping.select_row(1);
+        regrouping.move_commit(-1);
+        assert_eq!(regrouping.conflict_count(), 0);
+        assert_eq!(
+            summaries(&regrouping.into_branch_map()),
+            [
+                (
+                    "a".to_string(),
+                    vec!["add a".to_string(), "change a".to_string()]
+                ),
+                ("b".to_string(), vec!["add b".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reordering_a_dependent_commit_first_conflicts() {
+        let (_tempdir, repo, [base, add_a, _add_b, change_a]) = repository();
+        let branch_map: DisjointBranchMap = [branch(&repo, "a", &[add_a, change_a])]
+            .into_iter()
+            .collect();
+        let base = repo.find_commit(base).unwrap();
+        let mut regrouping = Regrouping::new(branch_map, &base, &repo);
+        assert_eq!(regrouping.conflict_count(), 0);
+
+        regrouping.select_row(1);
+        regrouping.reorder_commit(-1);
+        assert_eq!(regrouping.conflict_count(), 1);
+    }
+
+    #[test]
+    fn split_and_rename_groups() {
+        let (_tempdir, repo, [base, add_a, add_b, _change_a]) = repository();
+        let branch_map: DisjointBranchMap = [branch(&repo, "both", &[add_a, add_b])]
+            .into_iter()
+            .collect();
+        let base = repo.find_commit(base).unwrap();
+        let mut regrouping = Regrouping::new(branch_map, &base, &repo);
+
+        regrouping.select_row(1);
+        regrouping.split();
+        assert_eq!(
+            regrouping.rename("both"),
+            Err("another group already uses that branch name")
+        );
+        assert_eq!(regrouping.rename("  "), Err("branch name cannot be empty"));
+        for name in ["my branch", "a..b", "a~1", "a:b", "b.lock"] {
+            assert_eq!(
+                regrouping.rename(name),
+                Err("that is not a valid git branch name")
+            );
+        }
+        regrouping.rename("just-b").unwrap();
+        assert_eq!(
+            summaries(&regrouping.into_branch_map()),
+            [
+                ("both".to_string(), vec!["add a".to_string()]),
+                ("just-b".to_string(), vec!["add b".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn splitting_after_renaming_a_split_keeps_every_commit() {
+        let (_tempdir, repo, [base, add_a, add_b, change_a]) = repository();
+        let branch_map: DisjointBranchMap = [branch(&repo, "a", &[add_a, add_b, change_a])]
+            .into_iter()
+            .collect();
+        let base = repo.find_commit(base).unwrap();
+        let mut regrouping = Regrouping::new(branch_map, &base, &repo);
+
+        regrouping.select_row(2);
+        regrouping.split();
+        regrouping.rename("z").unwrap();
+        regrouping.select_column(-1);
+        regrouping.select_row(1);
+        regrouping.split();
+        assert_eq!(
+            summaries(&regrouping.into_branch_map()),
+            [
+                ("a".to_string(), vec!["add a".to_string()]),
+                ("a-3".to_string(), vec!["add b".to_string()]),
+                ("z".to_string(), vec!["change a".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn emptied_groups_are_dropped() {
+        let (_tempdir, repo, [base, add_a, add_b, _change_a]) = repository();
+        let branch_map: DisjointBranchMap =
+            [branch(&repo, "a", &[add_a]), branch(&repo, "b", &[add_b])]
+                .into_iter()
+                .collect();
+        let base = repo.find_commit(base).unwrap();
+        let mut regrouping = Regrouping::new(branch_map, &base, &repo);
+
+        regrouping.move_commit(1);
+        assert_eq!(
+            summaries(&regrouping.into_branch_map()),
+            [(
+                "b".to_string(),
+                vec!["add b".to_string(), "add a".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn borders_and_lines_fit_their_column() {
+        assert_eq!(border_line("a", "conflict", 12), "a───conflict");
+        assert_eq!(border_line("long-branch-name", "conflict", 8), "long-bra");
+        assert_eq!(fit("1234567 add a", 9), "1234567 a");
+        assert_eq!(fit("add a", 7), "add a  ");
+    }
+}
@@ -0,0 +1,119 @@
+//! Group commits by their Conventional Commits header, the way
+//! `--group-by scope` and `--group-by type-scope` do.
+//!
+//! A header has the form `<type>(<scope>)!: <description>`, where the scope
+//! and the `!` are optional, e.g. `fix(parser): handle empty input`.
+
+use crate::{cli::GroupBy, issue::regex, issue_group::GroupName};
+
+/// The type, scope and description of a Conventional Commits header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConventionalHeader<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub description: &'a str,
+}
+
+impl<'a> ConventionalHeader<'a> {
+    /// Parse the header of a commit summary, if it follows Conventional
+    /// Commits.
+    pub fn parse(summary: &'a str) -> Option<Self> {
+        let captures =
+            regex!(r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^()\s]+)\))?!?: (?P<description>\S.*)")
+                .captures(summary)?;
+        Some(Self {
+            kind: captures.name("type")?.as_str(),
+            scope: captures.name("scope").map(|scope| scope.as_str()),
+            description: captures.name("description")?.as_str(),
+        })
+    }
+
+    /// Name the commits of this type, e.g. `fixes` for `fix`.
+    fn kind_noun(&self) -> String {
+        match self.kind.to_lowercase().as_str() {
+            "feat" => "features".to_string(),
+            "fix" => "fixes".to_string(),
+            "perf" => "performance".to_string(),
+            "refactor" => "refactors".to_string(),
+            "chore" => "chores".to_string(),
+            "test" => "tests".to_string(),
+            "revert" => "reverts".to_string(),
+            kind => kind.to_string(),
+        }
+    }
+
+    /// The group of a commit with this header: its scope with `scope`, or
+    /// its scope and type with `type-scope`, e.g. `parser-fixes`. Commits
+    /// without a scope are grouped by type.
+    pub fn group_name(&self, group_by: GroupBy) -> Option<GroupName> {
+        let name = match (group_by, self.scope) {
+            (GroupBy::Scope, Some(scope)) => scope.to_lowercase(),
+            (GroupBy::TypeScope, Some(scope)) => {
+                format!("{}-{}", scope.to_lowercase(), self.kind_noun())
+            }
+            (GroupBy::Scope | GroupBy::TypeScope, None) => self.kind_noun(),
+            (GroupBy::Commit | GroupBy::Path, _) => return None,
+        };
+        Some(GroupName(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConventionalHeader;
+    use crate::{cli::GroupBy, issue_group::GroupName};
+
+    fn group_name(summary: &str, group_by: GroupBy) -> Option<GroupName> {
+        ConventionalHeader::parse(summary)?.group_name(group_by)
+    }
+
+    #[test]
+    fn parse_type_and_scope() {
+        assert_eq!(
+            ConventionalHeader::parse("fix(parser)!: handle empty input"),
+            Some(ConventionalHeader {
+                kind: "fix",
+                scope: Some("parser"),
+                description: "handle empty input",
+            })
+        );
+        assert_eq!(
+            ConventionalHeader::parse("docs: fix typo"),
+            Some(ConventionalHeader {
+                kind: "docs",
+                scope: None,
+                description: "fix typo",
+            })
+        );
+        assert_eq!(ConventionalHeader::parse("Fix the parser"), None);
+        assert_eq!(ConventionalHeader::parse("fix(parser):"), None);
+    }
+
+    #[test]
+    fn group_by_scope() {
+        assert_eq!(
+            group_name("fix(parser): handle empty input", GroupBy::Scope),
+            Some(GroupName("parser".to_string()))
+        );
+        assert_eq!(
+            group_name("docs: fix typo", GroupBy::Scope),
+            Some(GroupName("docs".to_string()))
+        );
+    }
+
+    #[test]
+    fn group_by_type_and_scope() {
+        assert_eq!(
+            group_name("fix(parser): handle empty input", GroupBy::TypeScope),
+            Some(GroupName("parser-fixes".to_string()))
+        );
+        assert_eq!(
+            group_name("feat(Parser): add comments", GroupBy::TypeScope),
+            Some(GroupName("parser-features".to_string()))
+        );
+        assert_eq!(
+            group_name("chore: bump deps", GroupBy::TypeScope),
+            Some(GroupName("chores".to_string()))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Stack issue groups whose commits cannot be cherry-picked apart, the way
+//! `--stack` does.
+//!
+//! When a branch cannot be built on the base because one of its commits
+//! conflicts on a path an earlier commit in another branch changed, and it
+//! can be built on that other branch instead, it is stacked on that branch.
+
+use std::{cmp::Reverse, fmt::Write};
+
+use git2::Commit;
+
+use crate::{
+    autosquash::SquashedCommit,
+    branch_name::BranchName,
+    disjoint_branch::{DisjointBranch, DisjointBranchMap},
+    git2_repository::HistoryOrder,
+    pre_validation,
+};
+
+/// Why one branch was stacked on another.
+#[derive(Debug)]
+pub struct Stacking {
+    pub branch_name: BranchName,
+    pub stacked_on: BranchName,
+    /// The commit that could not be picked onto the base.
+    pub commit_summary: String,
+    /// The commit in the other branch it depends on.
+    pub dependency_summary: String,
+    pub conflicting_paths: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct StackReport {
+    pub stackings: Vec<Stacking>,
+}
+
+impl StackReport {
+    pub fn is_empty(&self) -> bool {
+        self.stackings.is_empty()
+    }
+
+    pub fn render(&self, _use_color: bool) -> String {
+        let mut output = String::new();
+        for (i, stacking) in self.stackings.iter().enumerate() {
+            if i > 0 {
+                writeln!(output).unwrap();
+            }
+            writeln!(
+                output,
+                "note: stacked branch `{}` on `{}`",
+                stacking.branch_name, stacking.stacked_on
+            )
+            .unwrap();
+            writeln!(output, "  --> commit \"{}\"", stacking.commit_summary).unwrap();
+            writeln!(output, "   |").unwrap();
+            for path in &stacking.conflicting_paths {
+                writeln!(output, "   = conflict in {}", path).unwrap();
+            }
+            writeln!(output, "   |").unwrap();
+            writeln!(
+                output,
+                "   = note: it depends on commit \"{}\"",
+                stacking.dependency_summary
+            )
+            .unwrap();
+        }
+        output
+    }
+}
+
+/// Find the first unstacked branch that conflicts on the base, and that can
+/// be built on the branch holding the commit it depends on.
+fn next_stacking<'repo>(
+    branch_map: &DisjointBranchMap<'repo>,
+    base_commit: &Commit<'repo>,
+    history: &HistoryOrder,
+    repo: &'repo git2::Repository,
+) -> Option<(usize, Stacking)> {
+    branch_map
+        .iter()
+        .enumerate()
+        .filter(|(_index, (_issue_group, branch))| branch.stacked_on.is_none())
+        .find_map(|(index, (_issue_group, branch))| {
+            let commits = branch.commits.iter().flat_map(SquashedCommit::commits);
+            let conflict = pre_validation::simulate(commits, base_commit, repo).ok()??;
+            let mut candidates: Vec<(&DisjointBranch, &Commit)> = branch_map
+                .iter()
+                .map(|(_issue_group, other)| other)
+                // Never stack a branch on a branch stacked on it
+                .filter(|other| {
+                    !branch_map
+                        .stack(other)
+                        .iter()
+                        .any(|below| below.branch_name == branch.branch_name)
+                })
+                .filter_map(|other| {
+                    other
+                        .commits
+                        .iter()
+                        .flat_map(SquashedCommit::commits)
+                        .filter(|commit| conflict.is_caused_by(commit, repo))
+                        .max_by_key(|commit| history.key(commit))
+                        .map(|dependency| (other, dependency))
+                })
+                .collect();
+            // Prefer the branch holding the latest commit it depends on
+            candidates.sort_by_key(|(_parent, dependency)| Reverse(history.key(dependency)));
+            let (parent, dependency) = candidates.into_iter().find(|(parent, _dependency)| {
+                let stacked_commits = branch_map
+                    .stack_commits(parent)
+                    .chain(branch.commits.iter().flat_map(SquashedCommit::commits));
+                matches!(
+                    pre_validation::simulate(stacked_commits, base_commit, repo),
+                    Ok(None)
+                )
+            })?;
+            Some((
+                index,
+                Stacking {
+                    branch_name: branch.branch_name.clone(),
+                    stacked_on: parent.branch_name.clone(),
+                    commit_summary: conflict.commit.summary().unwrap_or("").to_string(),
+                    dependency_summary: dependency.summary().unwrap_or("").to_string(),
+                    conflicting_paths: conflict.conflicting_paths,
+                },
+            ))
+        })
+}
+
+/// The stack `branch` is part of, bottom first: the branches beneath it,
+/// `branch` itself, then the branches stacked on it while there is only one.
+pub fn stack_of(branch_map: &DisjointBranchMap, branch_name: &BranchName) -> Vec<BranchName> {
+    let Some(branch) = branch_map.find_by_branch_name(branch_name) else {
+        return Vec::new();
+    };
+    let mut stack: Vec<BranchName> = branch_map
+        .stack(branch)
+        .into_iter()
+        .map(|branch| branch.branch_name.clone())
+        .collect();
+    loop {
+        let mut children = branch_map
+            .iter()
+            .map(|(_issue_group, other)| other)
+            .filter(|other| other.stacked_on.as_ref() == stack.last());
+        match (children.next(), children.next()) {
+            (Some(child), None) if !stack.contains(&child.branch_name) => {
+                stack.push(child.branch_name.clone())
+            }
+            _ => break,
+        }
+    }
+    stack
+}
+
+/// Stack branches on the branches they depend on, until no unstacked branch
+/// can be fixed by stacking it.
+///
+/// Conflicts that stacking cannot resolve are left for
+/// [`pre_validation::validate`] to report.
+pub fn stack<'repo>(
+    mut branch_map: DisjointBranchMap<'repo>,
+    base_commit: &Commit<'repo>,
+    history: &HistoryOrder,
+    repo: &'repo git2::Repository,
+) -> (DisjointBranchMap<'repo>, StackReport) {
+    let mut report = StackReport::default();
+    while let Some((index, stacking)) = next_stacking(&branch_map, base_commit, history, repo) {
+        if let Some(branch) = branch_map.get_index_mut(index) {
+            branch.stacked_on = Some(stacking.stacked_on.clone());
+        }
+        report.stackings.push(stacking);
+    }
+    branch_map.sort_stacks();
+    (branch_map, report)
+}
@@ -0,0 +1,223 @@
+//! Links to work tracker issues, configured per project with
+//! `disjoint.trackerUrl`.
+//!
+//! Each entry has the form `<project>:<template>`, where `<template>` is a URL
+//! containing `{id}`, e.g. `ABC:https://acme.atlassian.net/browse/{id}`. The
+//! project is the part of an issue key before its first hyphen.
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::{
+    issue::{Issue, IssueReference},
+    issue_group::IssueGroup,
+};
+
+/// Placeholder replaced with the issue key, e.g. `ABC-123`.
+const ISSUE_KEY_PLACEHOLDER: &str = "{id}";
+
+/// The URL template for the issues of one work tracker project.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrackerUrl {
+    project: String,
+    template: String,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ParseTrackerUrlError {
+    value: String,
+    kind: ParseTrackerUrlErrorKind,
+}
+
+impl Display for ParseTrackerUrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseTrackerUrlErrorKind::MissingProject => write!(
+                f,
+                "tracker URL {:?} should have the form <project>:<template>",
+                self.value
+            ),
+            ParseTrackerUrlErrorKind::MissingPlaceholder => write!(
+                f,
+                "tracker URL {:?} should contain {ISSUE_KEY_PLACEHOLDER}",
+                self.value
+            ),
+        }
+    }
+}
+
+impl Error for ParseTrackerUrlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseTrackerUrlErrorKind::MissingProject => None,
+            ParseTrackerUrlErrorKind::MissingPlaceholder => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseTrackerUrlErrorKind {
+    #[non_exhaustive]
+    MissingProject,
+    #[non_exhaustive]
+    MissingPlaceholder,
+}
+
+impl FromStr for TrackerUrl {
+    type Err = ParseTrackerUrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |kind| ParseTrackerUrlError {
+            value: s.to_owned(),
+            kind,
+        };
+        let (project, template) = s
+            .split_once(':')
+            // A value without a project splits inside the URL's scheme.
+            .filter(|(project, template)| !project.trim().is_empty() && !template.starts_with("//"))
+            .ok_or_else(|| error(ParseTrackerUrlErrorKind::MissingProject))?;
+        if !template.contains(ISSUE_KEY_PLACEHOLDER) {
+            return Err(error(ParseTrackerUrlErrorKind::MissingPlaceholder));
+        }
+        Ok(Self {
+            project: project.trim().to_uppercase(),
+            template: template.trim().to_owned(),
+        })
+    }
+}
+
+/// Every configured tracker URL.
+#[derive(Clone, Debug, Default)]
+pub struct TrackerUrls {
+    tracker_urls: Vec<TrackerUrl>,
+}
+
+impl FromIterator<TrackerUrl> for TrackerUrls {
+    fn from_iter<T: IntoIterator<Item = TrackerUrl>>(iter: T) -> Self {
+        Self {
+            tracker_urls: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl TrackerUrls {
+    /// The URL of a work tracker issue, if its project has a tracker URL.
+    pub fn url(&self, issue: &Issue) -> Option<String> {
+        let Issue::WorkTracker(key) = issue else {
+            return None;
+        };
+        let (project, _) = key.split_once('-')?;
+        self.tracker_urls
+            .iter()
+            .find(|tracker_url| tracker_url.project == project)
+            .map(|tracker_url| tracker_url.template.replace(ISSUE_KEY_PLACEHOLDER, key))
+    }
+
+    /// Describe an issue group for the terminal, followed by the issue's URL
+    /// when there is one.
+    pub fn render_issue_group(&self, issue_group: &IssueGroup) -> String {
+        let url = match issue_group {
+            IssueGroup::Issue(issue) => self.url(issue),
+            IssueGroup::Commit(_) | IssueGroup::Named(_) | IssueGroup::Combined(_) => None,
+        };
+        match url {
+            Some(url) => format!("{issue_group} ({url})"),
+            None => issue_group.to_string(),
+        }
+    }
+
+    /// Render a PR footer line, linking the issue key when there is a URL,
+    /// e.g. `Closes [ABC-3](https://acme.atlassian.net/browse/ABC-3)`.
+    pub fn render_reference(&self, reference: &IssueReference) -> String {
+        match self.url(&reference.issue) {
+            Some(url) => format!(
+                "{} [{}]({url})",
+                reference.intent.keyword(),
+                reference.issue.reference()
+            ),
+            None => reference.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TrackerUrl, TrackerUrls};
+    use crate::{
+        issue::{Issue, IssueIntent, IssueReference},
+        issue_group::IssueGroup,
+    };
+
+    fn tracker_urls() -> TrackerUrls {
+        [
+            "ABC:https://acme.atlassian.net/browse/{id}",
+            "eng:https://linear.app/acme/issue/{id}",
+        ]
+        .iter()
+        .map(|value| value.parse::<TrackerUrl>().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn url_for_configured_project() {
+        assert_eq!(
+            tracker_urls().url(&Issue::WorkTracker("ABC-123".to_string())),
+            Some("https://acme.atlassian.net/browse/ABC-123".to_string())
+        );
+        assert_eq!(
+            tracker_urls().url(&Issue::WorkTracker("ENG-42".to_string())),
+            Some("https://linear.app/acme/issue/ENG-42".to_string())
+        );
+    }
+
+    #[test]
+    fn no_url_for_other_projects_or_github_issues() {
+        assert_eq!(
+            tracker_urls().url(&Issue::WorkTracker("OPS-1".to_string())),
+            None
+        );
+        assert_eq!(tracker_urls().url(&Issue::GitHub("123".to_string())), None);
+    }
+
+    #[test]
+    fn render_issue_group_with_url() {
+        assert_eq!(
+            tracker_urls()
+                .render_issue_group(&IssueGroup::Issue(Issue::WorkTracker("ABC-1".to_string()))),
+            "Issue ABC-1 (https://acme.atlassian.net/browse/ABC-1)"
+        );
+        assert_eq!(
+            tracker_urls()
+                .render_issue_group(&IssueGroup::Issue(Issue::WorkTracker("OPS-1".to_string()))),
+            "Issue OPS-1"
+        );
+    }
+
+    #[test]
+    fn render_linked_reference() {
+        assert_eq!(
+            tracker_urls().render_reference(&IssueReference {
+                issue: Issue::WorkTracker("ABC-3".to_string()),
+                intent: IssueIntent::Closes,
+            }),
+            "Closes [ABC-3](https://acme.atlassian.net/browse/ABC-3)"
+        );
+        assert_eq!(
+            tracker_urls().render_reference(&IssueReference {
+                issue: Issue::GitHub("12".to_string()),
+                intent: IssueIntent::References,
+            }),
+            "Refs #12"
+        );
+    }
+
+    #[test]
+    fn parse_requires_project_and_placeholder() {
+        assert!("https://acme.atlassian.net/browse/{id}"
+            .parse::<TrackerUrl>()
+            .is_err());
+        assert!("ABC:https://acme.atlassian.net/browse/"
+            .parse::<TrackerUrl>()
+            .is_err());
+    }
+}
@@ -1,15 +1,27 @@
-use std::collections::BTreeMap;
+// Each test binary uses only some of these helpers
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use git2::{Commit, Oid, Signature, Time};
 use tempfile::TempDir;
 
-use git_disjoint::cli::{CommitGrouping, CommitsToConsider, OverlayCommitsIntoOnePullRequest};
+use git_disjoint::auto_combine;
+use git_disjoint::branch_template::{self, BranchNaming};
+use git_disjoint::cli::{
+    CombineDependentGroups, CommitGrouping, CommitsToConsider, GroupBy, MultipleIssuePolicy,
+    OverlayCommitsIntoOnePullRequest, PromptUserToChooseCommits, StackDependentGroups,
+};
+use git_disjoint::config::Config;
 use git_disjoint::default_branch::DefaultBranch;
+use git_disjoint::dependency;
 use git_disjoint::disjoint_branch::DisjointBranchMap;
-use git_disjoint::git2_repository::Repository;
-use git_disjoint::issue_group_map::IssueGroupMap;
+use git_disjoint::git2_repository::{self, HistoryOrder, Repository};
+use git_disjoint::issue_group_map::{IssueGroupMap, Selection};
+use git_disjoint::local_commit;
 use git_disjoint::pre_validation;
+use git_disjoint::stack;
 
 const FIXED_TIME: i64 = 1_000_000_000;
 const FIXED_OFFSET: i32 = 0;
@@ -17,8 +29,11 @@ const FIXED_OFFSET: i32 = 0;
 #[derive(Debug)]
 pub struct TestFixture {
     pub title: String,
+    pub config: Vec<(String, String)>,
     pub base_files: BTreeMap<String, String>,
     pub commits: Vec<TestCommit>,
+    /// Commits on `origin/main` after the base, which HEAD does not have.
+    pub upstream: Vec<TestCommit>,
     pub run_args: Vec<String>,
 }
 
@@ -43,6 +58,26 @@ pub fn parse_fixture(kdl: &str) -> TestFixture {
         .expect("title must be a string")
         .to_string();
 
+    let config = doc
+        .nodes()
+        .iter()
+        .filter(|node| node.name().value() == "config")
+        .map(|node| {
+            let entries: Vec<_> = node.entries().iter().collect();
+            let key = entries[0]
+                .value()
+                .as_string()
+                .expect("config key must be a string")
+                .to_string();
+            let value = entries[1]
+                .value()
+                .as_string()
+                .expect("config value must be a string")
+                .to_string();
+            (key, value)
+        })
+        .collect();
+
     let mut base_files = BTreeMap::new();
     if let Some(base_node) = doc.get("base") {
         if let Some(children) = base_node.children() {
@@ -66,8 +101,10 @@ pub fn parse_fixture(kdl: &str) -> TestFixture {
     }
 
     let mut commits = Vec::new();
+    let mut upstream = Vec::new();
     for node in doc.nodes() {
-        if node.name().value() == "commit" {
+        let kind = node.name().value();
+        if kind == "commit" || kind == "upstream" {
             let message = node
                 .entries()
                 .first()
@@ -113,11 +150,15 @@ pub fn parse_fixture(kdl: &str) -> TestFixture {
                 }
             }
 
-            commits.push(TestCommit {
+            let commit = TestCommit {
                 message,
                 files,
                 delete,
-            });
+            };
+            match kind {
+                "upstream" => upstream.push(commit),
+                _ => commits.push(commit),
+            }
         }
     }
 
@@ -134,8 +175,10 @@ pub fn parse_fixture(kdl: &str) -> TestFixture {
 
     TestFixture {
         title,
+        config,
         base_files,
         commits,
+        upstream,
         run_args,
     }
 }
@@ -147,30 +190,68 @@ fn parse_cli_args(run_str: &str) -> Vec<String> {
     tokens.map(|s| s.to_string()).collect()
 }
 
-fn resolve_cli_args(
-    args: &[String],
-) -> (
-    CommitsToConsider,
-    CommitGrouping,
-    OverlayCommitsIntoOnePullRequest,
-) {
-    let mut all = CommitsToConsider::WithTrailer;
-    let mut separate = CommitGrouping::ByIssue;
-    let mut overlay = OverlayCommitsIntoOnePullRequest::No;
-
-    for arg in args {
+struct FixtureArgs {
+    all: CommitsToConsider,
+    separate: CommitGrouping,
+    overlay: OverlayCommitsIntoOnePullRequest,
+    auto_combine: CombineDependentGroups,
+    stack: StackDependentGroups,
+    multiple_issues: MultipleIssuePolicy,
+    group_by: GroupBy,
+    selection: Selection,
+    combine: Vec<String>,
+}
+
+fn resolve_cli_args(args: &[String]) -> FixtureArgs {
+    let mut resolved = FixtureArgs {
+        all: CommitsToConsider::WithTrailer,
+        separate: CommitGrouping::ByIssue,
+        overlay: OverlayCommitsIntoOnePullRequest::No,
+        auto_combine: CombineDependentGroups::No,
+        stack: StackDependentGroups::No,
+        multiple_issues: MultipleIssuePolicy::Primary,
+        group_by: GroupBy::Commit,
+        selection: Selection::default(),
+        combine: Vec::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().expect("fixture arg requires a value").as_str();
         match arg.as_str() {
-            "--all" | "-a" => all = CommitsToConsider::All,
-            "--separate" | "-s" => separate = CommitGrouping::Individual,
-            "--overlay" | "-o" => overlay = OverlayCommitsIntoOnePullRequest::Yes,
+            "--all" | "-a" => resolved.all = CommitsToConsider::All,
+            "--separate" | "-s" => resolved.separate = CommitGrouping::Individual,
+            "--overlay" | "-o" => resolved.overlay = OverlayCommitsIntoOnePullRequest::Yes,
+            "--auto-combine" => resolved.auto_combine = CombineDependentGroups::Yes,
+            "--stack" => resolved.stack = StackDependentGroups::Yes,
+            "--group-by" => {
+                resolved.group_by = match value() {
+                    "commit" => GroupBy::Commit,
+                    "path" => GroupBy::Path,
+                    "scope" => GroupBy::Scope,
+                    "type-scope" => GroupBy::TypeScope,
+                    other => panic!("unknown --group-by value: {other}"),
+                }
+            }
+            "--issue" => resolved.selection.issues.push(value().to_string()),
+            "--exclude-issue" => resolved.selection.excluded_issues.push(value().to_string()),
+            "--commit" => resolved.selection.commits.push(value().to_string()),
+            "--combine" => resolved.combine.push(value().to_string()),
+            "--multiple-issues" => {
+                resolved.multiple_issues = match value() {
+                    "primary" => MultipleIssuePolicy::Primary,
+                    "refuse" => MultipleIssuePolicy::Refuse,
+                    other => panic!("unknown --multiple-issues value: {other}"),
+                }
+            }
             other => panic!("unknown fixture arg: {other}"),
         }
     }
 
-    (all, separate, overlay)
+    resolved
 }
 
-fn fixed_signature() -> Signature<'static> {
+pub fn fixed_signature() -> Signature<'static> {
     Signature::new(
         "Test User",
         "test@test.com",
@@ -179,6 +260,28 @@ fn fixed_signature() -> Signature<'static> {
     .unwrap()
 }
 
+/// Commit `files` on top of `parent`, without touching HEAD, the index or the
+/// working tree.
+pub fn make_commit(
+    repo: &git2::Repository,
+    parent: Option<Oid>,
+    files: &[(&str, &str)],
+    message: &str,
+) -> Oid {
+    let sig = fixed_signature();
+    let parent = parent.map(|parent| repo.find_commit(parent).unwrap());
+    let parent_tree = parent.as_ref().map(|parent| parent.tree().unwrap());
+    let mut builder = repo.treebuilder(parent_tree.as_ref()).unwrap();
+    for (filename, content) in files {
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        builder.insert(filename, blob, 0o100644).unwrap();
+    }
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(None, &sig, &sig, message, &tree, &parents)
+        .unwrap()
+}
+
 fn write_file(repo_path: &Path, relative_path: &str, content: &str) {
     let full_path = repo_path.join(relative_path);
     if let Some(parent) = full_path.parent() {
@@ -232,6 +335,17 @@ fn build_test_repo(fixture: &TestFixture) -> TestRepo {
 
     let sig = fixed_signature();
 
+    // Write fixture configuration to the repository's local git config the
+    // same way a user would, so values are escaped exactly as git escapes them
+    for (key, value) in &fixture.config {
+        let status = std::process::Command::new("git")
+            .args(["config", "--local", "--add", key, value])
+            .current_dir(tempdir.path())
+            .status()
+            .unwrap();
+        assert!(status.success(), "unable to set git config {key}");
+    }
+
     // Create initial commit with base files
     let mut index = git2_repo.index().unwrap();
 
@@ -256,9 +370,26 @@ fn build_test_repo(fixture: &TestFixture) -> TestRepo {
             .unwrap()
     };
 
+    // Commit the upstream commits, then rewind HEAD to the base
+    let mut upstream_oid = base_oid;
+    for test_commit in &fixture.upstream {
+        let parent = git2_repo.find_commit(upstream_oid).unwrap();
+        upstream_oid = create_git2_commit(
+            &git2_repo,
+            &parent,
+            &test_commit.files,
+            &test_commit.delete,
+            &test_commit.message,
+        );
+    }
+    if upstream_oid != base_oid {
+        let base = git2_repo.find_object(base_oid, None).unwrap();
+        git2_repo.reset(&base, git2::ResetType::Hard, None).unwrap();
+    }
+
     // Create the remote ref that git-disjoint looks for
     git2_repo
-        .reference("refs/remotes/origin/main", base_oid, true, "test setup")
+        .reference("refs/remotes/origin/main", upstream_oid, true, "test setup")
         .unwrap();
 
     // Now apply each test commit
@@ -284,66 +415,187 @@ fn build_test_repo(fixture: &TestFixture) -> TestRepo {
 
 pub fn run_fixture(fixture: &TestFixture) -> String {
     let test_repo = build_test_repo(fixture);
-    let (all, separate, overlay) = resolve_cli_args(&fixture.run_args);
+    let FixtureArgs {
+        all,
+        separate,
+        overlay,
+        auto_combine,
+        stack,
+        multiple_issues,
+        group_by,
+        selection,
+        combine,
+    } = resolve_cli_args(&fixture.run_args);
 
     let base_commit = test_repo
         .repo
         .base_commit(&DefaultBranch("main".to_string()))
         .unwrap();
 
-    let commits: Vec<Commit> = test_repo
-        .repo
-        .commits_since_base(&base_commit)
-        .unwrap()
-        .collect();
+    let config = match Config::try_from(&test_repo.repo.config().unwrap()) {
+        Ok(config) => config,
+        Err(e) => return format!("exit: 1\n\nerror: {e}"),
+    };
+
+    // Skip commits already upstream
+    let (commits, upstream) = test_repo.repo.commits_since_base(&base_commit).unwrap();
+    let history: HistoryOrder = commits.iter().collect();
+
+    // Hold back local-only commits
+    let (commits, held_back) = config
+        .local_commit_patterns
+        .partition(commits, &config.issue_patterns);
 
     // Build the issue group map
-    let commits_by_issue_group = match IssueGroupMap::try_from_commits(commits, all, separate) {
+    let commits_by_issue_group = match IssueGroupMap::try_from_commits(
+        commits,
+        &config,
+        all,
+        separate,
+        multiple_issues,
+        group_by,
+        &test_repo.repo,
+    ) {
+        Ok(map) => map,
+        Err(e) => return format!("exit: 1\n\nerror: {e}"),
+    };
+
+    // Narrow the groups to the issues and commits selected on the command
+    // line. The selection menu is never shown, since a selection is given
+    let commits_by_issue_group = match selection.is_empty() {
+        true => commits_by_issue_group,
+        false => match commits_by_issue_group.select_issues(
+            PromptUserToChooseCommits::No,
+            overlay,
+            &selection,
+            &test_repo.repo,
+        ) {
+            Ok(map) => map,
+            Err(e) => return format!("exit: 1\n\nerror: {e}"),
+        },
+    };
+
+    // Combine the groups named together with --combine
+    let commits_by_issue_group = match commits_by_issue_group.apply_combinations(&combine, &history)
+    {
         Ok(map) => map.apply_overlay(overlay),
         Err(e) => return format!("exit: 1\n\nerror: {e}"),
     };
 
-    // Build the branch map
-    let branch_map: DisjointBranchMap = match commits_by_issue_group.try_into() {
+    // Combine groups that depend on each other
+    let (commits_by_issue_group, combine_report) = match auto_combine {
+        CombineDependentGroups::Yes => auto_combine::combine(
+            commits_by_issue_group,
+            &base_commit,
+            &history,
+            &test_repo.repo,
+        ),
+        CombineDependentGroups::No => (commits_by_issue_group, Default::default()),
+    };
+
+    // Build the branch map, with a fixed user and date for branch templates
+    let naming = BranchNaming {
+        template: config.branch_template.clone(),
+        max_length: config.branch_max_length,
+        user: "test-user".to_string(),
+        date: branch_template::date(FIXED_TIME),
+    };
+    let branch_map = match DisjointBranchMap::try_from_issue_group_map(
+        commits_by_issue_group,
+        &HashMap::new(),
+        &naming,
+    ) {
+        Ok(map) => map,
+        Err(e) => return format!("exit: 1\n\nerror: {e}"),
+    };
+
+    // Stack branches on the branches they depend on
+    let (branch_map, stack_report) = match stack {
+        StackDependentGroups::Yes => {
+            stack::stack(branch_map, &base_commit, &history, &test_repo.repo)
+        }
+        StackDependentGroups::No => (branch_map, Default::default()),
+    };
+
+    // Build branches on the branches they depend on
+    let branch_map = match dependency::apply(branch_map, &config.issue_patterns) {
         Ok(map) => map,
         Err(e) => return format!("exit: 1\n\nerror: {e}"),
     };
 
+    // Render held-back commits and the warnings about them ahead of the result
+    let held_back_report =
+        pre_validation::held_back_dependencies(&branch_map, &held_back, &test_repo.repo);
+    let notes: String = [
+        git2_repository::render_already_upstream(&upstream),
+        local_commit::render_held_back(&held_back),
+        held_back_report.render(false),
+        combine_report.render(false),
+        stack_report.render(false),
+    ]
+    .iter()
+    .filter(|section| !section.is_empty())
+    .map(|section| format!("{}\n\n", section.trim_end()))
+    .collect();
+
     // Validate
     if let Err(report) = pre_validation::validate(&branch_map, &base_commit, &test_repo.repo) {
-        return format!("exit: 1\n\n{}", report.render(false).trim_end());
+        return format!(
+            "exit: 1\n\n{notes}{}",
+            report
+                .render(false, overlay, auto_combine, stack)
+                .trim_end()
+        );
     }
 
     if branch_map.is_empty() {
-        return "exit: 0\n\n(no branches planned)".to_string();
+        return format!("exit: 0\n\n{notes}(no branches planned)");
     }
 
     // Execute: create branches via in-memory cherry-pick and render per-branch
     use std::fmt::Write;
     let mut output = "exit: 0".to_string();
+    if !notes.is_empty() {
+        write!(output, "\n\n{}", notes.trim_end()).unwrap();
+    }
 
     for (_issue_group, branch) in branch_map.iter() {
-        let mut simulated_head = base_commit.clone();
-
-        for commit in &branch.commits {
-            let mut index = test_repo
+        // Build stacked branches on the branch beneath them
+        let mut simulated_head = match &branch.stacked_on {
+            Some(parent) => test_repo
                 .repo
-                .cherrypick_commit(commit, &simulated_head, 0, None)
-                .unwrap();
+                .find_branch(parent.as_str(), git2::BranchType::Local)
+                .unwrap()
+                .get()
+                .peel_to_commit()
+                .unwrap(),
+            None => base_commit.clone(),
+        };
+
+        for squashed in &branch.commits {
+            // Pick the commit and its fixups, then squash them into one commit
+            let parent = simulated_head.clone();
+            let mut tree = parent.tree().unwrap();
+            for commit in squashed.commits() {
+                let mut index = test_repo
+                    .repo
+                    .cherrypick_commit(commit, &simulated_head, 0, None)
+                    .unwrap();
+
+                let tree_oid = index.write_tree_to(&test_repo.repo).unwrap();
+                tree = test_repo.repo.find_tree(tree_oid).unwrap();
+                let sig = fixed_signature();
+                let new_oid = test_repo
+                    .repo
+                    .commit(None, &sig, &sig, "picked", &tree, &[&simulated_head])
+                    .unwrap();
+                simulated_head = test_repo.repo.find_commit(new_oid).unwrap();
+            }
 
-            let tree_oid = index.write_tree_to(&test_repo.repo).unwrap();
-            let tree = test_repo.repo.find_tree(tree_oid).unwrap();
             let sig = fixed_signature();
             let new_oid = test_repo
                 .repo
-                .commit(
-                    None,
-                    &sig,
-                    &sig,
-                    commit.summary().unwrap_or(""),
-                    &tree,
-                    &[&simulated_head],
-                )
+                .commit(None, &sig, &sig, &squashed.message(), &tree, &[&parent])
                 .unwrap();
             simulated_head = test_repo.repo.find_commit(new_oid).unwrap();
         }
@@ -355,9 +607,24 @@ pub fn run_fixture(fixture: &TestFixture) -> String {
             .unwrap();
 
         // Render branch header
-        write!(output, "\n\nbranch {}:", branch.branch_name).unwrap();
-        for commit in &branch.commits {
-            write!(output, "\n  * {}", commit.summary().unwrap_or("")).unwrap();
+        match &branch.stacked_on {
+            Some(parent) => write!(
+                output,
+                "\n\nbranch {} (stacked on {parent}):",
+                branch.branch_name
+            ),
+            None => write!(output, "\n\nbranch {}:", branch.branch_name),
+        }
+        .unwrap();
+        for dependency in &branch.depends_on {
+            write!(output, "\n  depends on {dependency}").unwrap();
+        }
+        for squashed in &branch.commits {
+            let message = squashed.message();
+            write!(output, "\n  * {}", message.lines().next().unwrap_or("")).unwrap();
+            for fixup in &squashed.fixups {
+                write!(output, "\n    + {}", fixup.commit.summary().unwrap_or("")).unwrap();
+            }
         }
 
         // List files in the branch's tree
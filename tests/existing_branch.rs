@@ -0,0 +1,256 @@
+mod common;
+
+use git2::{Oid, Repository as Git2Repository};
+use tempfile::TempDir;
+
+use git_disjoint::autosquash;
+use git_disjoint::branch_name::BranchName;
+use git_disjoint::disjoint_branch::{DisjointBranch, DisjointBranchMap};
+use git_disjoint::git2_repository::{Repository, SOURCE_NOTES_REF};
+use git_disjoint::issue_group::{GroupName, IssueGroup};
+use git_disjoint::log_file::LogFile;
+use git_disjoint::pre_validation;
+
+use common::{fixed_signature, make_commit};
+
+/// A base commit, and local commits adding `a.txt`, fixing it up, and adding
+/// `b.txt`.
+fn repository() -> (TempDir, Repository, Oid, Vec<Oid>) {
+    let tempdir = TempDir::new().unwrap();
+    let repo = Git2Repository::init(tempdir.path()).unwrap();
+    let base = make_commit(&repo, None, &[(".gitkeep", "")], "initial commit");
+    let a = make_commit(&repo, Some(base), &[("a.txt", "a")], "feat: add a");
+    let fixup = make_commit(
+        &repo,
+        Some(a),
+        &[("a.txt", "a, fixed")],
+        "fixup! feat: add a",
+    );
+    let b = make_commit(&repo, Some(fixup), &[("b.txt", "b")], "feat: add b");
+    (tempdir, repo.into(), base, vec![a, fixup, b])
+}
+
+fn missing_commits(repo: &Repository, branch: Oid, base: Oid, local: &[Oid]) -> Vec<Oid> {
+    let commits = autosquash::fold(local.iter().map(|oid| repo.find_commit(*oid).unwrap()));
+    let missing = repo
+        .missing_commits(
+            &repo.find_commit(branch).unwrap(),
+            &repo.find_commit(base).unwrap(),
+            &commits,
+        )
+        .unwrap();
+    commits
+        .iter()
+        .map(|commit| commit.commit.id())
+        .filter(|oid| missing.contains(oid))
+        .collect()
+}
+
+#[test]
+fn commit_with_the_same_patch_is_on_the_branch() {
+    let (_tempdir, repo, base, local) = repository();
+    // The branch holds `a` as first picked, before its fixup
+    let branch = make_commit(&repo, Some(base), &[("a.txt", "a")], "feat: add a (#1)");
+    assert_eq!(missing_commits(&repo, branch, base, &local), [local[2]]);
+}
+
+#[test]
+fn commit_squashed_with_its_fixups_is_on_the_branch() {
+    let (_tempdir, repo, base, local) = repository();
+    let branch = make_commit(&repo, Some(base), &[("a.txt", "a, fixed")], "feat: add a");
+    assert_eq!(missing_commits(&repo, branch, base, &local), [local[2]]);
+}
+
+#[test]
+fn branch_without_the_planned_commits_is_missing_all_of_them() {
+    let (_tempdir, repo, base, local) = repository();
+    let branch = make_commit(&repo, Some(base), &[("c.txt", "c")], "feat: add c");
+    assert_eq!(
+        missing_commits(&repo, branch, base, &local),
+        [local[0], local[2]]
+    );
+}
+
+fn outdated_commits(repo: &Repository, branch: Oid, base: Oid, local: &[Oid]) -> Vec<Oid> {
+    let commits = autosquash::fold(local.iter().map(|oid| repo.find_commit(*oid).unwrap()));
+    let outdated = repo
+        .outdated_commits(
+            &repo.find_commit(branch).unwrap(),
+            &repo.find_commit(base).unwrap(),
+            &commits,
+        )
+        .unwrap();
+    local
+        .iter()
+        .copied()
+        .filter(|oid| outdated.contains(oid))
+        .collect()
+}
+
+const MESSAGE: &str = "feat: add a\n\nChange-Id: I0123abcd\n";
+
+#[test]
+fn amended_commit_is_outdated() {
+    let tempdir = TempDir::new().unwrap();
+    let repo: Repository = Git2Repository::init(tempdir.path()).unwrap().into();
+    let base = make_commit(&repo, None, &[(".gitkeep", "")], "initial commit");
+    let published = make_commit(&repo, Some(base), &[("a.txt", "a")], MESSAGE);
+    let amended = make_commit(&repo, Some(base), &[("a.txt", "a, reviewed")], MESSAGE);
+    assert_eq!(
+        outdated_commits(&repo, published, base, &[amended]),
+        [amended]
+    );
+    assert!(outdated_commits(&repo, amended, base, &[amended]).is_empty());
+}
+
+#[test]
+fn reworded_commit_is_outdated() {
+    let tempdir = TempDir::new().unwrap();
+    let repo: Repository = Git2Repository::init(tempdir.path()).unwrap().into();
+    let base = make_commit(&repo, None, &[(".gitkeep", "")], "initial commit");
+    let published = make_commit(&repo, Some(base), &[("a.txt", "a")], MESSAGE);
+    let reworded = make_commit(
+        &repo,
+        Some(base),
+        &[("a.txt", "a")],
+        "feat: add the a file\n\nChange-Id: I0123abcd\n",
+    );
+    assert_eq!(
+        outdated_commits(&repo, published, base, &[reworded]),
+        [reworded]
+    );
+}
+
+/// Record under the source notes ref that `published` was picked from
+/// `source`, as git-disjoint does when it picks a commit onto a branch.
+fn record_source(repo: &Repository, published: Oid, source: Oid) {
+    let sig = fixed_signature();
+    repo.note(
+        &sig,
+        &sig,
+        Some(SOURCE_NOTES_REF),
+        published,
+        &source.to_string(),
+        true,
+    )
+    .unwrap();
+}
+
+#[test]
+fn commit_without_change_id_is_matched_by_its_recorded_source() {
+    let (_tempdir, repo, base, local) = repository();
+    let branch = make_commit(&repo, Some(base), &[("a.txt", "a, fixed")], "feat: add a");
+    // Without a recorded source, there is nothing to match on
+    assert!(outdated_commits(&repo, branch, base, &local).is_empty());
+
+    // Picked from `a` itself, the branch commit is up to date
+    record_source(&repo, branch, local[0]);
+    assert!(outdated_commits(&repo, branch, base, &local).is_empty());
+
+    // Picked from an earlier version of `a`, before it was amended
+    let original = make_commit(&repo, Some(base), &[("a.txt", "a, draft")], "feat: add a");
+    let branch = make_commit(&repo, Some(base), &[("a.txt", "a, draft")], "feat: add a");
+    record_source(&repo, branch, original);
+    assert_eq!(outdated_commits(&repo, branch, base, &local), [local[0]]);
+}
+
+const A_LINES: &str = "a1\na2\na3\na4\na5\n";
+
+#[test]
+fn range_diff_ignores_changes_to_the_base() {
+    let tempdir = TempDir::new().unwrap();
+    let repo: Repository = Git2Repository::init(tempdir.path()).unwrap().into();
+    let old_base = make_commit(&repo, None, &[(".gitkeep", "")], "initial commit");
+    let published = make_commit(&repo, Some(old_base), &[("a.txt", A_LINES)], MESSAGE);
+    let new_base = make_commit(&repo, Some(old_base), &[("z.txt", "z\n")], "feat: add z");
+    let rebuilt = make_commit(&repo, Some(new_base), &[("a.txt", A_LINES)], MESSAGE);
+    let amended = make_commit(
+        &repo,
+        Some(new_base),
+        &[("a.txt", &A_LINES.replace("a3", "a3, reviewed"))],
+        MESSAGE,
+    );
+    let log_file = LogFile(tempdir.path().join("log"));
+    let range_diff = |head: Oid| {
+        let commit = |oid: Oid| repo.find_commit(oid).unwrap();
+        repo.render_range_diff(
+            (&commit(old_base), &commit(published)),
+            (&commit(new_base), &commit(head)),
+            &log_file,
+        )
+        .unwrap()
+    };
+
+    let diff = range_diff(rebuilt);
+    assert!(diff.contains(" = "), "{diff}");
+    assert!(!diff.contains("z.txt"), "{diff}");
+
+    let diff = range_diff(amended);
+    assert!(diff.contains(" ! "), "{diff}");
+    assert!(diff.contains("-+a3\n"), "{diff}");
+    assert!(diff.contains("++a3, reviewed\n"), "{diff}");
+    assert!(!diff.contains("z.txt"), "{diff}");
+}
+
+#[test]
+fn range_diff_shows_reworded_messages() {
+    let tempdir = TempDir::new().unwrap();
+    let repo: Repository = Git2Repository::init(tempdir.path()).unwrap().into();
+    let base = make_commit(&repo, None, &[(".gitkeep", "")], "initial commit");
+    let published = make_commit(&repo, Some(base), &[("a.txt", "a\n")], MESSAGE);
+    let reworded = make_commit(
+        &repo,
+        Some(base),
+        &[("a.txt", "a\n")],
+        "feat: add the a file\n\nChange-Id: I0123abcd\n",
+    );
+    let commit = |oid: Oid| repo.find_commit(oid).unwrap();
+    let diff = repo
+        .render_range_diff(
+            (&commit(base), &commit(published)),
+            (&commit(base), &commit(reworded)),
+            &LogFile(tempdir.path().join("log")),
+        )
+        .unwrap();
+    assert!(diff.contains("-    feat: add a\n"), "{diff}");
+    assert!(diff.contains("+    feat: add the a file\n"), "{diff}");
+}
+
+#[test]
+fn missing_commits_are_validated_onto_the_existing_branch() {
+    let tempdir = TempDir::new().unwrap();
+    let git2_repo = Git2Repository::init(tempdir.path()).unwrap();
+    let base = make_commit(&git2_repo, None, &[(".gitkeep", "")], "initial commit");
+    let a = make_commit(&git2_repo, Some(base), &[("a.txt", "a\n")], "feat: add a");
+    let b = make_commit(&git2_repo, Some(a), &[("b.txt", "b\n")], "feat: add b");
+    let repo: Repository = git2_repo.into();
+    let branch_map: DisjointBranchMap = [(
+        IssueGroup::Named(GroupName("ab".to_string())),
+        DisjointBranch {
+            branch_name: BranchName::new("ab".to_string()),
+            commits: autosquash::fold([a, b].map(|oid| repo.find_commit(oid).unwrap())),
+            stacked_on: None,
+            depends_on: Vec::new(),
+        },
+    )]
+    .into_iter()
+    .collect();
+    let base_commit = repo.find_commit(base).unwrap();
+    assert!(pre_validation::validate(&branch_map, &base_commit, &repo).is_ok());
+
+    // The published branch holds `a`, and someone else's change to `b.txt`,
+    // so picking the missing `b` onto it conflicts
+    let published_a = make_commit(&repo, Some(base), &[("a.txt", "a\n")], "feat: add a");
+    let published = make_commit(
+        &repo,
+        Some(published_a),
+        &[("b.txt", "b, elsewhere\n")],
+        "feat: add b elsewhere",
+    );
+    repo.branch("ab", &repo.find_commit(published).unwrap(), false)
+        .unwrap();
+    let report = pre_validation::validate(&branch_map, &base_commit, &repo).unwrap_err();
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].commit_summary, "feat: add b");
+    assert_eq!(report.conflicts[0].conflicting_paths, ["b.txt"]);
+}
@@ -63,8 +63,40 @@ fn editor_template_orders_commits_most_recent_first() {
         .base_commit(&DefaultBranch("main".to_string()))
         .unwrap();
 
-    let commits: Vec<git2::Commit> = repo.commits_since_base(&base_commit).unwrap().collect();
+    let (commits, _upstream) = repo.commits_since_base(&base_commit).unwrap();
     let template: PullRequestMessageTemplate = commits.iter().collect();
 
     insta::assert_snapshot!(format!("{template}"));
 }
+
+#[test]
+fn editor_template_offers_issue_title() {
+    let tempdir = TempDir::new().unwrap();
+    let git2_repo = Git2Repository::init(tempdir.path()).unwrap();
+    let sig = fixed_signature();
+
+    let tree_oid = git2_repo.index().unwrap().write_tree().unwrap();
+    let base_oid = {
+        let tree = git2_repo.find_tree(tree_oid).unwrap();
+        git2_repo
+            .commit(Some("HEAD"), &sig, &sig, "initial commit", &tree, &[])
+            .unwrap()
+    };
+    let a = make_commit(
+        &git2_repo,
+        base_oid,
+        "a.txt",
+        "fix: handle empty input\n\nCloses #42",
+    );
+    let commit = git2_repo.find_commit(a).unwrap();
+
+    let template = [&commit]
+        .into_iter()
+        .collect::<PullRequestMessageTemplate>()
+        .with_title(Some("Parser crashes on empty input".to_string()));
+
+    assert!(format!("{template}").starts_with(&format!(
+        "Parser crashes on empty input\n\n{}",
+        git_disjoint::pull_request_message::IGNORE_MARKER
+    )));
+}